@@ -0,0 +1,86 @@
+use super::{expression::Expression, FshAst};
+use serde::Serialize;
+
+/// Represents an operator of an arithmetic expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ArithmeticOperator {
+    /// +
+    Add,
+
+    /// -
+    Subtract,
+
+    /// *
+    Multiply,
+
+    /// /
+    Divide,
+
+    /// %
+    Remainder,
+
+    /// **
+    Power,
+
+    /// ==
+    Equal,
+
+    /// !=
+    NotEqual,
+
+    /// <
+    LessThan,
+
+    /// <=
+    LessThanOrEqual,
+
+    /// >
+    GreaterThan,
+
+    /// >=
+    GreaterThanOrEqual,
+
+    /// !
+    Not,
+}
+
+impl FshAst for ArithmeticOperator {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+/// Represents an arithmetic expression, e.g. `$n + 1` or `(2 ** 8) % 7`.
+///
+/// Comparisons evaluate to `1` if true, otherwise `0`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Arithmetic {
+    /// An integer literal.
+    Number(isize),
+
+    /// A variable, written with or without a leading `$`.
+    Variable(String),
+
+    /// An expansion whose value is used as a number (e.g., `${#NAME}` or `$(wc -l < file)`).
+    Expansion(Box<Expression>),
+
+    /// A unary operation (`-`, `+`, or `!`).
+    Unary(ArithmeticOperator, Box<Arithmetic>),
+
+    /// A binary operation.
+    Binary(ArithmeticOperator, Box<Arithmetic>, Box<Arithmetic>),
+}
+
+impl FshAst for Arithmetic {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::FshAst;
+use super::{arithmetic::Arithmetic, statement::Statement, FshAst};
 use serde::Serialize;
 
 /// Represents a literal or expression in the FSH AST.
@@ -10,6 +10,10 @@ pub enum Expression {
     /// A string literal.
     String(String),
 
+    /// Quoted or escaped literal text in a word (e.g., `"*.rs"`), kept out of brace,
+    /// tilde and glob expansion.
+    Quoted(String),
+
     /// A numeric literal.
     Number(isize),
 
@@ -21,6 +25,44 @@ pub enum Expression {
 
     /// A file descriptor (e.g., `@1` or `@2`).
     FileDescriptor(i32),
+
+    /// An identifier with an accessor (e.g., `$paths[0]` or `$cfg.key`),
+    /// holding the identifier and the accessor as written.
+    Index(String, String),
+
+    /// A list value (e.g., `[a b c]`).
+    List(Vec<Expression>),
+
+    /// A map value (e.g., `{k: v, k2: v2}`), with its entries in the written order.
+    Map(Vec<(String, Expression)>),
+
+    /// A command substitution (e.g., `$(pwd)`), replaced by the output of the statement.
+    Substitution(Box<Statement>),
+
+    /// An input process substitution (e.g., `<(sort a.txt)`), replaced by a `/dev/fd/N` path
+    /// from which the output of the statement is read.
+    InputSubstitution(Box<Statement>),
+
+    /// An output process substitution (e.g., `>(gzip > out.gz)`), replaced by a `/dev/fd/N` path
+    /// whose writes are the input of the statement.
+    OutputSubstitution(Box<Statement>),
+
+    /// A brace group (e.g., `{ date; uptime; }`) in place of a command name,
+    /// run in the current shell.
+    Group(Box<Statement>),
+
+    /// A subshell (e.g., `(cd /tmp; ls)`) in place of a command name, whose changes
+    /// to the shell state and variables are discarded when it ends.
+    Subshell(Box<Statement>),
+
+    /// A braced parameter expansion (e.g., `${HOME:-/root}`).
+    Parameter(Parameter),
+
+    /// An arithmetic expression (e.g., `$((1 + 2))` or the value in `$n = $n + 1`).
+    Arithmetic(Box<Arithmetic>),
+
+    /// A word made of adjacent parts (e.g., `"$HOME"/bin`), joined into a single string.
+    Word(Vec<Expression>),
 }
 
 impl FshAst for Expression {
@@ -32,3 +74,109 @@ impl FshAst for Expression {
         }
     }
 }
+
+/// Represents the operator of a braced parameter expansion.
+///
+/// The `is_null_checked` flag is set for the colon forms (e.g., `:-`),
+/// which also treat an empty value as unset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ParameterOperator {
+    /// `${VAR}`
+    Value,
+
+    /// `${#VAR}`
+    Length,
+
+    /// `${VAR:-word}`: the word if unset.
+    Default {
+        word: Vec<Expression>,
+        is_null_checked: bool,
+    },
+
+    /// `${VAR:=word}`: the word if unset, which is also assigned to the variable.
+    Assign {
+        word: Vec<Expression>,
+        is_null_checked: bool,
+    },
+
+    /// `${VAR:?word}`: an error with the word as the message if unset.
+    Error {
+        word: Vec<Expression>,
+        is_null_checked: bool,
+    },
+
+    /// `${VAR:+word}`: the word if set, otherwise an empty string.
+    Alternative {
+        word: Vec<Expression>,
+        is_null_checked: bool,
+    },
+
+    /// `${VAR:offset}` or `${VAR:offset:length}`
+    Substring {
+        offset: isize,
+        length: Option<isize>,
+    },
+
+    /// `${VAR#pattern}` or `${VAR##pattern}`
+    RemovePrefix {
+        pattern: Vec<Expression>,
+        is_longest: bool,
+    },
+
+    /// `${VAR%pattern}` or `${VAR%%pattern}`
+    RemoveSuffix {
+        pattern: Vec<Expression>,
+        is_longest: bool,
+    },
+}
+
+impl FshAst for ParameterOperator {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+/// Represents a braced parameter expansion, such as `${HOME:-/root}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Parameter {
+    name: String,
+    operator: ParameterOperator,
+}
+
+impl Parameter {
+    /// Creates a new `Parameter`.
+    ///
+    /// # Arguments
+    /// - `name` - The name of the variable or positional parameter.
+    /// - `operator` - The operator applied to its value.
+    ///
+    /// # Returns
+    /// A new `Parameter` instance.
+    pub fn new(name: String, operator: ParameterOperator) -> Self {
+        Self { name, operator }
+    }
+
+    /// Returns the name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the operator of the expansion.
+    pub fn operator(&self) -> &ParameterOperator {
+        &self.operator
+    }
+}
+
+impl FshAst for Parameter {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod arithmetic;
 pub mod expression;
 pub mod statement;
 
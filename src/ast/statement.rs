@@ -2,7 +2,7 @@ use super::{expression::Expression, FshAst};
 use serde::Serialize;
 use std::collections::VecDeque;
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Statement {
     Sequence(Sequence),
 
@@ -13,6 +13,22 @@ pub enum Statement {
     Command(Command),
 
     Pipe(Pipe),
+
+    Logical(Logical),
+
+    If(If),
+
+    While(While),
+
+    For(For),
+
+    Break,
+
+    Continue,
+
+    Function(Function),
+
+    Return(Option<Expression>),
 }
 
 impl FshAst for Statement {
@@ -25,7 +41,7 @@ impl FshAst for Statement {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Sequence(VecDeque<Statement>);
 
 impl Sequence {
@@ -55,7 +71,7 @@ impl FshAst for Sequence {
 /// Represents an assignment operation.
 ///
 /// An `Assignment` consists of an identifier and a value.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Assignment {
     identifier: Expression,
     value: Expression,
@@ -95,14 +111,29 @@ impl FshAst for Assignment {
     }
 }
 
-/// Represents redirection operators (`>` and `<`).
+/// Represents redirection operators (`<`, `>`, `>>`, `>|`, `<>`, `<<` and `<<<`).
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum RedirectOperator {
-    /// <
+    /// <, reads from an existing file.
     LessThan,
 
-    /// >
+    /// >, truncates or creates the file, unless the `noclobber` option protects it.
     GreaterThan,
+
+    /// >>, appends to or creates the file.
+    DoubleGreaterThan,
+
+    /// >|, truncates or creates the file, even if the `noclobber` option is set.
+    GreaterThanPipe,
+
+    /// <>, opens or creates the file for reading and writing.
+    LessThanGreaterThan,
+
+    /// <<, a here-document whose body is the right-hand expression.
+    HereDocument,
+
+    /// <<<, a here-string whose right-hand expression is followed by a newline.
+    HereString,
 }
 
 impl FshAst for RedirectOperator {
@@ -127,7 +158,7 @@ impl Redirect {
     /// Creates a new `Redirect`.
     ///
     /// # Arguments
-    /// - `operator` - The redirection operator (`>` or `<`).
+    /// - `operator` - The redirection operator (e.g., `>`, `>>` or `<`).
     /// - `left` - The left-hand expression (typically a file descriptor).
     /// - `right` - The right-hand expression (typically a filename or another descriptor).
     ///
@@ -168,8 +199,11 @@ impl FshAst for Redirect {
 }
 
 /// Represents a command.
-#[derive(Debug, PartialEq, Serialize)]
+///
+/// The assignments before the name (e.g., `RUST_LOG=debug cargo run`) apply to the command alone.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Command {
+    assignments: Vec<Assignment>,
     name: Expression,
     arguments: Vec<Expression>,
     redirects: Vec<Redirect>,
@@ -180,6 +214,7 @@ impl Command {
     /// Creates a new `Command`.
     ///
     /// # Arguments
+    /// - `assignments` - The assignments before the command name.
     /// - `name` - The command name.
     /// - `arguments` - A list of arguments.
     /// - `redirects` - A list of redirections.
@@ -188,12 +223,14 @@ impl Command {
     /// # Returns
     /// A new `Command` instance.
     pub fn new(
+        assignments: Vec<Assignment>,
         name: Expression,
         arguments: Vec<Expression>,
         redirects: Vec<Redirect>,
         is_background: Expression,
     ) -> Self {
         Self {
+            assignments,
             name,
             arguments,
             redirects,
@@ -201,6 +238,11 @@ impl Command {
         }
     }
 
+    /// Returns a reference to the assignments before the command name.
+    pub fn assignments(&self) -> &Vec<Assignment> {
+        &self.assignments
+    }
+
     /// Returns a reference to the command name.
     pub fn name(&self) -> &Expression {
         &self.name
@@ -236,7 +278,7 @@ impl FshAst for Command {
 ///
 /// Internally, this is implemented as a `VecDeque<Command>` to allow
 /// efficient push and pop operations at both ends.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Pipe(VecDeque<Command>);
 
 impl Pipe {
@@ -265,6 +307,15 @@ impl Pipe {
         self.0.pop_front()
     }
 
+    /// Returns the command at the end of the pipe.
+    ///
+    /// # Returns
+    /// - `Some(&Command)` if a command was present.
+    /// - `None` if the pipe is empty.
+    pub fn back(&self) -> Option<&Command> {
+        self.0.back()
+    }
+
     /// Checks whether the pipe contains no commands.
     ///
     /// # Returns
@@ -283,3 +334,285 @@ impl FshAst for Pipe {
         }
     }
 }
+
+/// Represents logical operators (`&&` and `||`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum LogicalOperator {
+    /// &&
+    And,
+
+    /// ||
+    Or,
+}
+
+impl FshAst for LogicalOperator {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+/// Represents an AND/OR list, e.g. `make && ./run || notify`.
+///
+/// The right-hand statement is executed depending on the exit status of the left-hand one.
+/// Lists are left-associative, so `a && b || c` is `(a && b) || c`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Logical {
+    operator: LogicalOperator,
+    left: Box<Statement>,
+    right: Box<Statement>,
+}
+
+impl Logical {
+    /// Creates a new `Logical`.
+    ///
+    /// # Arguments
+    /// - `operator` - The logical operator (`&&` or `||`).
+    /// - `left` - The statement executed first.
+    /// - `right` - The statement executed depending on the result of `left`.
+    ///
+    /// # Returns
+    /// A new `Logical` instance.
+    pub fn new(operator: LogicalOperator, left: Statement, right: Statement) -> Self {
+        Self {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Returns a reference to the logical operator.
+    pub fn operator(&self) -> &LogicalOperator {
+        &self.operator
+    }
+
+    /// Returns a reference to the left-hand statement.
+    pub fn left(&self) -> &Statement {
+        &self.left
+    }
+
+    /// Returns a reference to the right-hand statement.
+    pub fn right(&self) -> &Statement {
+        &self.right
+    }
+}
+
+impl FshAst for Logical {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+/// Represents a conditional block, e.g. `if <condition> { ... } else { ... }`.
+///
+/// The condition is a statement whose exit status decides which branch is executed.
+/// An `else if` chain is represented by an alternative that is itself an `If`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct If {
+    condition: Box<Statement>,
+    consequence: Box<Statement>,
+    alternative: Option<Box<Statement>>,
+}
+
+impl If {
+    /// Creates a new `If`.
+    ///
+    /// # Arguments
+    /// - `condition` - The statement whose exit status is tested.
+    /// - `consequence` - The statement executed if the condition succeeds.
+    /// - `alternative` - The statement executed if the condition fails, if any.
+    ///
+    /// # Returns
+    /// A new `If` instance.
+    pub fn new(
+        condition: Statement,
+        consequence: Statement,
+        alternative: Option<Statement>,
+    ) -> Self {
+        Self {
+            condition: Box::new(condition),
+            consequence: Box::new(consequence),
+            alternative: alternative.map(Box::new),
+        }
+    }
+
+    /// Returns a reference to the condition.
+    pub fn condition(&self) -> &Statement {
+        &self.condition
+    }
+
+    /// Returns a reference to the statement executed if the condition succeeds.
+    pub fn consequence(&self) -> &Statement {
+        &self.consequence
+    }
+
+    /// Returns a reference to the statement executed if the condition fails, if any.
+    pub fn alternative(&self) -> Option<&Statement> {
+        self.alternative.as_deref()
+    }
+}
+
+impl FshAst for If {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+/// Represents a `while` loop, e.g. `while <condition> { ... }`.
+///
+/// The body is executed repeatedly as long as the condition exits successfully.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct While {
+    condition: Box<Statement>,
+    body: Box<Statement>,
+}
+
+impl While {
+    /// Creates a new `While`.
+    ///
+    /// # Arguments
+    /// - `condition` - The statement whose exit status is tested before each iteration.
+    /// - `body` - The statement executed on each iteration.
+    ///
+    /// # Returns
+    /// A new `While` instance.
+    pub fn new(condition: Statement, body: Statement) -> Self {
+        Self {
+            condition: Box::new(condition),
+            body: Box::new(body),
+        }
+    }
+
+    /// Returns a reference to the condition.
+    pub fn condition(&self) -> &Statement {
+        &self.condition
+    }
+
+    /// Returns a reference to the body.
+    pub fn body(&self) -> &Statement {
+        &self.body
+    }
+}
+
+impl FshAst for While {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+/// Represents a `for` loop, e.g. `for $x in a b c { ... }`.
+///
+/// The items are expanded like command arguments, and the body is executed
+/// once per resulting word with the identifier bound to it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct For {
+    identifier: Expression,
+    items: Vec<Expression>,
+    body: Box<Statement>,
+}
+
+impl For {
+    /// Creates a new `For`.
+    ///
+    /// # Arguments
+    /// - `identifier` - The loop variable.
+    /// - `items` - The items to iterate over.
+    /// - `body` - The statement executed on each iteration.
+    ///
+    /// # Returns
+    /// A new `For` instance.
+    pub fn new(identifier: Expression, items: Vec<Expression>, body: Statement) -> Self {
+        Self {
+            identifier,
+            items,
+            body: Box::new(body),
+        }
+    }
+
+    /// Returns a reference to the loop variable.
+    pub fn identifier(&self) -> &Expression {
+        &self.identifier
+    }
+
+    /// Returns a reference to the list of items.
+    pub fn items(&self) -> &Vec<Expression> {
+        &self.items
+    }
+
+    /// Returns a reference to the body.
+    pub fn body(&self) -> &Statement {
+        &self.body
+    }
+}
+
+impl FshAst for For {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+/// Represents a function definition, e.g. `fn mkcd { mkdir $1; cd $1 }`.
+///
+/// Arguments passed to the function are available as positional parameters (`$1`, `$2`, ...)
+/// while the body runs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Function {
+    name: Expression,
+    body: Box<Statement>,
+}
+
+impl Function {
+    /// Creates a new `Function`.
+    ///
+    /// # Arguments
+    /// - `name` - The function name.
+    /// - `body` - The statement executed when the function is called.
+    ///
+    /// # Returns
+    /// A new `Function` instance.
+    pub fn new(name: Expression, body: Statement) -> Self {
+        Self {
+            name,
+            body: Box::new(body),
+        }
+    }
+
+    /// Returns a reference to the function name.
+    pub fn name(&self) -> &Expression {
+        &self.name
+    }
+
+    /// Returns a reference to the body.
+    pub fn body(&self) -> &Statement {
+        &self.body
+    }
+}
+
+impl FshAst for Function {
+    fn to_json(&self, is_pretty: bool) -> String {
+        if is_pretty {
+            serde_json::to_string_pretty(self).unwrap()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
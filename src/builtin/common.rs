@@ -1,7 +1,15 @@
-use crate::{error::*, result::Result, utils};
+use crate::{
+    error::*,
+    result::Result,
+    sh_vars::{ShVars, Value},
+    utils,
+};
 use std::{
+    collections::BTreeSet,
     env,
-    ffi::OsStr,
+    ffi::{CString, OsStr},
+    fs,
+    os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
     process,
 };
@@ -48,3 +56,598 @@ pub fn cd<S: AsRef<OsStr> + ?Sized>(p: &S, current_path: &Path) -> Result<PathBu
 
     Ok(path)
 }
+
+/// The shell options that can be set with `set -o`.
+pub const SHELL_OPTIONS: &[&str] = &[
+    "noclobber",
+    "nullglob",
+    "failglob",
+    "dotglob",
+    "nocaseglob",
+    "globstar",
+    "pipefail",
+];
+
+/// Sets or unsets shell options.
+///
+/// Supported arguments:
+/// - `-o name` sets the option, `+o name` unsets it.
+/// - `-C` and `+C` set and unset `noclobber`.
+///
+/// # Arguments
+/// - `args`: The arguments of `set`.
+/// - `options`: The names of the shell options that are set.
+///
+/// # Returns
+/// - `Ok(())` if all arguments were applied.
+/// - `Err(Error)` for an unknown option or a missing option name.
+pub fn set(args: &[String], options: &mut BTreeSet<String>) -> Result<()> {
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (name, is_set) = match arg.as_str() {
+            "-C" => ("noclobber", true),
+            "+C" => ("noclobber", false),
+            "-o" | "+o" => {
+                let name = args.next().ok_or(Error::new(
+                    ErrorKind::Other,
+                    format!("set: {arg}: option name required"),
+                ))?;
+
+                (name.as_str(), arg == "-o")
+            }
+            _ => Err(Error::new(
+                ErrorKind::Other,
+                format!("set: {arg}: invalid option"),
+            ))?,
+        };
+
+        if !SHELL_OPTIONS.contains(&name) {
+            Err(Error::new(
+                ErrorKind::Other,
+                format!("set: {name}: invalid option name"),
+            ))?
+        }
+
+        match is_set {
+            true => options.insert(name.to_string()),
+            false => options.remove(name),
+        };
+    }
+
+    Ok(())
+}
+
+/// Removes the first positional arguments, so `$2` becomes `$1` (`shift`) or `$n+1`
+/// becomes `$1` (`shift n`).
+///
+/// # Arguments
+/// - `args`: The arguments of `shift`.
+/// - `arguments`: The positional arguments.
+///
+/// # Returns
+/// - `Ok(0)` if the arguments were shifted.
+/// - `Ok(1)` if the count is greater than the number of positional arguments,
+///   which are then left unchanged.
+/// - `Err(Error)` if the count is not a number.
+pub fn shift(args: &[String], arguments: &mut Vec<String>) -> Result<i32> {
+    let count = match args.first() {
+        Some(arg) => arg.parse::<usize>().map_err(|_| {
+            Error::new(
+                ErrorKind::Other,
+                format!("shift: {arg}: numeric argument required"),
+            )
+        })?,
+        None => 1,
+    };
+
+    if count > arguments.len() {
+        return Ok(1);
+    }
+
+    arguments.drain(..count);
+
+    Ok(0)
+}
+
+/// Checks whether a string is a valid variable name: letters, digits and underscores,
+/// not starting with a digit.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Splits an argument of `export` or `local` into a name and an optional value (`NAME=value`).
+fn split_assignment<'a>(builtin: &str, arg: &'a str) -> Result<(&'a str, Option<&'a str>)> {
+    let (name, value) = match arg.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (arg, None),
+    };
+
+    if !is_name(name) {
+        Err(Error::new(
+            ErrorKind::Other,
+            format!("{builtin}: {arg}: not a valid identifier"),
+        ))?
+    }
+
+    Ok((name, value))
+}
+
+/// Exports variables to child processes (`export NAME` or `export NAME=value`),
+/// or lists the exported variables when there are no arguments.
+///
+/// # Arguments
+/// - `args`: The arguments of `export`.
+/// - `sh_vars`: The shell variables.
+///
+/// # Returns
+/// - `Ok(Vec<String>)` with the lines to print, as `export NAME=value` sorted by name.
+/// - `Err(Error)` if a name is not a valid identifier.
+pub fn export(args: &[String], sh_vars: &mut ShVars) -> Result<Vec<String>> {
+    if args.is_empty() {
+        return Ok(env(sh_vars)
+            .into_iter()
+            .map(|line| format!("export {line}"))
+            .collect());
+    }
+
+    for arg in args {
+        let (name, value) = split_assignment("export", arg)?;
+
+        if let Some(value) = value {
+            sh_vars.insert_value(name, Value::String(value.to_string()))?;
+        }
+
+        sh_vars.export(name)?;
+    }
+
+    Ok(Vec::new())
+}
+
+/// Removes variables (`unset NAME...`), including their export flags.
+///
+/// # Arguments
+/// - `args`: The names of the variables.
+/// - `sh_vars`: The shell variables.
+///
+/// # Returns
+/// - `Ok(())` if the variables were removed (or were not set).
+/// - `Err(Error)` if a name is not a valid identifier.
+pub fn unset(args: &[String], sh_vars: &mut ShVars) -> Result<()> {
+    for arg in args {
+        if !is_name(arg) {
+            Err(Error::new(
+                ErrorKind::Other,
+                format!("unset: {arg}: not a valid identifier"),
+            ))?
+        }
+
+        sh_vars.remove(arg);
+    }
+
+    Ok(())
+}
+
+/// Declares variables local to the current function or block (`local NAME` or
+/// `local NAME=value`), so their previous values are restored when it ends.
+///
+/// # Arguments
+/// - `args`: The arguments of `local`.
+/// - `sh_vars`: The shell variables.
+///
+/// # Returns
+/// - `Ok(())` if the variables were declared.
+/// - `Err(Error)` if a name is not a valid identifier, or outside a function or block.
+pub fn local(args: &[String], sh_vars: &mut ShVars) -> Result<()> {
+    for arg in args {
+        let (name, value) = split_assignment("local", arg)?;
+
+        sh_vars.declare_local(name).map_err(|_| {
+            Error::new(
+                ErrorKind::Other,
+                "local: can only be used in a function or block",
+            )
+        })?;
+
+        if let Some(value) = value {
+            sh_vars.insert_value(name, Value::String(value.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists the environment passed to child processes, as printed by `env`.
+///
+/// # Arguments
+/// - `sh_vars`: The shell variables.
+///
+/// # Returns
+/// The exported variables as `NAME=value` lines sorted by name,
+/// with lists and maps flattened by `Value::flatten`.
+pub fn env(sh_vars: &ShVars) -> Vec<String> {
+    let mut lines = sh_vars
+        .environment()
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>();
+
+    lines.sort();
+
+    lines
+}
+
+/// Lists all shell variables, exported or not, as printed by `set` without arguments.
+///
+/// # Arguments
+/// - `sh_vars`: The shell variables.
+///
+/// # Returns
+/// The variables as `NAME=value` lines sorted by name, where lists are written
+/// as `[a b c]` and maps as `{k: v, ...}`.
+pub fn variables(sh_vars: &ShVars) -> Vec<String> {
+    let mut names = sh_vars.keys();
+
+    names.sort();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let value = match sh_vars.get_value(name)? {
+                Value::String(string) => string.to_string(),
+                Value::List(list) => format!("[{}]", list.join(" ")),
+                Value::Map(map) => format!(
+                    "{{{}}}",
+                    map.iter()
+                        .map(|(key, value)| format!("{key}: {value}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            };
+
+            Some(format!("{name}={value}"))
+        })
+        .collect()
+}
+
+/// Evaluates a conditional expression, as in `test -f file` or `[ $a = b ]`.
+///
+/// Supported expressions:
+/// - File checks: `-e`, `-f`, `-d`, `-x`, `-r`, `-w`, `-s`
+/// - Strings: `s1 = s2`, `s1 == s2`, `s1 != s2`, `-n s`, `-z s`, and `s` (non-empty)
+/// - Integers: `-eq`, `-ne`, `-lt`, `-le`, `-gt`, `-ge`
+/// - Logic: `!`, `-a` (and), `-o` (or), and `( ... )`, where `-a` binds tighter than `-o`.
+///
+/// Relative paths are resolved against `current_path`.
+///
+/// # Arguments
+/// - `args`: The expression, one operand or operator per argument.
+/// - `current_path`: The base directory for resolving relative paths.
+///
+/// # Returns
+/// - `Ok(bool)` with the result of the expression.
+/// - `Err(Error)` if the expression is malformed or an integer operand is invalid.
+pub fn test(args: &[String], current_path: &Path) -> Result<bool> {
+    // with a single argument, even an operator such as `-n` is just a non-empty string.
+    if args.len() <= 1 {
+        return Ok(args.first().is_some_and(|arg| !arg.is_empty()));
+    }
+
+    let mut test = Test {
+        args,
+        index: 0,
+        current_path,
+    };
+
+    let result = test.parse_or()?;
+
+    match args.get(test.index) {
+        Some(arg) => Err(test_error(format!("{arg}: unexpected operator"))),
+        None => Ok(result),
+    }
+}
+
+/// A recursive descent evaluator over the arguments of `test`.
+struct Test<'a> {
+    args: &'a [String],
+    index: usize,
+    current_path: &'a Path,
+}
+
+impl<'a> Test<'a> {
+    fn peek(&self, offset: usize) -> Option<&str> {
+        self.args.get(self.index + offset).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<&'a str> {
+        let arg = self
+            .args
+            .get(self.index)
+            .ok_or_else(|| test_error("argument expected"))?;
+
+        self.index += 1;
+
+        Ok(arg)
+    }
+
+    fn parse_or(&mut self) -> Result<bool> {
+        let mut result = self.parse_and()?;
+
+        while self.peek(0) == Some("-o") {
+            self.index += 1;
+
+            // both sides are always parsed so that syntax errors are reported.
+            result = self.parse_and()? || result;
+        }
+
+        Ok(result)
+    }
+
+    fn parse_and(&mut self) -> Result<bool> {
+        let mut result = self.parse_not()?;
+
+        while self.peek(0) == Some("-a") {
+            self.index += 1;
+
+            result = self.parse_not()? && result;
+        }
+
+        Ok(result)
+    }
+
+    fn parse_not(&mut self) -> Result<bool> {
+        // `! = x` compares the string `!`, rather than negating `= x`.
+        if self.peek(0) == Some("!") && !self.peek(1).is_some_and(is_binary_operator) {
+            self.index += 1;
+
+            return Ok(!self.parse_not()?);
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<bool> {
+        if self.peek(1).is_some_and(is_binary_operator) && self.peek(2).is_some() {
+            let left = self.next()?;
+
+            let operator = self.next()?;
+
+            let right = self.next()?;
+
+            return compare(left, operator, right);
+        }
+
+        if self.peek(0) == Some("(") {
+            self.index += 1;
+
+            let result = self.parse_or()?;
+
+            if self.next()? != ")" {
+                Err(test_error("`)` expected"))?
+            }
+
+            return Ok(result);
+        }
+
+        let arg = self.next()?;
+
+        if is_unary_operator(arg) && self.peek(0).is_some() {
+            let operand = self.next()?;
+
+            return Ok(match arg {
+                "-n" => !operand.is_empty(),
+                "-z" => operand.is_empty(),
+                _ => check_file(arg, &self.current_path.join(operand)),
+            });
+        }
+
+        Ok(!arg.is_empty())
+    }
+}
+
+fn is_unary_operator(arg: &str) -> bool {
+    matches!(
+        arg,
+        "-e" | "-f" | "-d" | "-x" | "-r" | "-w" | "-s" | "-n" | "-z"
+    )
+}
+
+fn is_binary_operator(arg: &str) -> bool {
+    matches!(
+        arg,
+        "=" | "==" | "!=" | "-eq" | "-ne" | "-lt" | "-le" | "-gt" | "-ge"
+    )
+}
+
+fn compare(left: &str, operator: &str, right: &str) -> Result<bool> {
+    let parse = |operand: &str| {
+        operand
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| test_error(format!("{operand}: integer expression expected")))
+    };
+
+    Ok(match operator {
+        "=" | "==" => left == right,
+        "!=" => left != right,
+        "-eq" => parse(left)? == parse(right)?,
+        "-ne" => parse(left)? != parse(right)?,
+        "-lt" => parse(left)? < parse(right)?,
+        "-le" => parse(left)? <= parse(right)?,
+        "-gt" => parse(left)? > parse(right)?,
+        "-ge" => parse(left)? >= parse(right)?,
+        _ => Err(test_error(format!("{operator}: unknown operator")))?,
+    })
+}
+
+fn check_file(operator: &str, path: &Path) -> bool {
+    let access = |mode| {
+        CString::new(path.as_os_str().as_bytes())
+            .is_ok_and(|path| unsafe { libc::access(path.as_ptr(), mode) } == 0)
+    };
+
+    match operator {
+        "-e" => path.exists(),
+        "-f" => path.is_file(),
+        "-d" => path.is_dir(),
+        "-s" => fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0),
+        "-x" => access(libc::X_OK),
+        "-r" => access(libc::R_OK),
+        "-w" => access(libc::W_OK),
+        _ => false,
+    }
+}
+
+fn test_error(message: impl Into<String>) -> Error {
+    Error::new(ErrorKind::Other, format!("test: {}", message.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &str) -> Result<bool> {
+        let args = args
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        test(&args, Path::new("/"))
+    }
+
+    #[test]
+    fn test_test_strings() {
+        assert!(run("abc = abc").unwrap());
+        assert!(run("abc != abd").unwrap());
+        assert!(!run("-z x").unwrap());
+        assert!(run("-n x").unwrap());
+        assert!(run("-n").unwrap());
+        assert!(!run("").unwrap());
+    }
+
+    #[test]
+    fn test_test_integers() {
+        assert!(run("10 -gt 9").unwrap());
+        assert!(run("-1 -lt 0").unwrap());
+        assert!(!run("3 -ne 3").unwrap());
+        assert!(run("a -eq 1").is_err());
+    }
+
+    #[test]
+    fn test_test_files() {
+        assert!(run("-d tmp").unwrap());
+        assert!(run("-e /").unwrap());
+        assert!(!run("-f /").unwrap());
+        assert!(!run("-e /nonexistent/file").unwrap());
+    }
+
+    #[test]
+    fn test_test_logic() {
+        assert!(run("! 1 -eq 2").unwrap());
+        assert!(run("1 -eq 2 -o 2 -eq 2").unwrap());
+        assert!(!run("1 -eq 1 -a 1 -eq 2").unwrap());
+        assert!(run("( 1 -eq 2 -o 1 -eq 1 ) -a x").unwrap());
+        assert!(run("1 -eq 1 x").is_err());
+        assert!(run("( x").is_err());
+    }
+
+    #[test]
+    fn test_set() {
+        let mut options = BTreeSet::new();
+
+        let args = |args: &str| {
+            args.split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+
+        set(&args("-o noclobber"), &mut options).unwrap();
+        assert!(options.contains("noclobber"));
+
+        set(&args("+C"), &mut options).unwrap();
+        assert!(options.is_empty());
+
+        set(&args("-o pipefail -o nullglob"), &mut options).unwrap();
+        assert!(options.contains("pipefail") && options.contains("nullglob"));
+
+        set(&args("+o pipefail"), &mut options).unwrap();
+        assert!(!options.contains("pipefail"));
+
+        assert!(set(&args("-o"), &mut options).is_err());
+        assert!(set(&args("-o unknown"), &mut options).is_err());
+        assert!(set(&args("-x"), &mut options).is_err());
+    }
+
+    #[test]
+    fn test_shift() {
+        let mut arguments = vec![String::from("a"), String::from("b"), String::from("c")];
+
+        assert_eq!(shift(&[], &mut arguments).unwrap(), 0);
+        assert_eq!(arguments, ["b", "c"]);
+
+        assert_eq!(shift(&[String::from("3")], &mut arguments).unwrap(), 1);
+        assert_eq!(arguments, ["b", "c"]);
+
+        assert_eq!(shift(&[String::from("2")], &mut arguments).unwrap(), 0);
+        assert!(arguments.is_empty());
+
+        assert!(shift(&[String::from("x")], &mut arguments).is_err());
+    }
+
+    #[test]
+    fn test_export_and_unset() {
+        let mut sh_vars = ShVars::new();
+
+        let args = |args: &str| {
+            args.split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+
+        sh_vars.insert("b", "2").unwrap();
+
+        assert!(export(&args("a=1 b"), &mut sh_vars).unwrap().is_empty());
+        assert_eq!(
+            export(&[], &mut sh_vars).unwrap(),
+            ["export a=1", "export b=2"]
+        );
+
+        sh_vars.insert("c", "3").unwrap();
+        assert_eq!(env(&sh_vars), ["a=1", "b=2"]);
+        assert_eq!(variables(&sh_vars), ["a=1", "b=2", "c=3"]);
+
+        unset(&args("a c"), &mut sh_vars).unwrap();
+        assert_eq!(variables(&sh_vars), ["b=2"]);
+
+        // an empty value stays empty.
+        export(&args("e="), &mut sh_vars).unwrap();
+        assert_eq!(env(&sh_vars), ["b=2", "e="]);
+
+        assert!(export(&args("1a=x"), &mut sh_vars).is_err());
+        assert!(unset(&args("a-b"), &mut sh_vars).is_err());
+    }
+
+    #[test]
+    fn test_local() {
+        let mut sh_vars = ShVars::new();
+
+        sh_vars.insert("x", "outer").unwrap();
+
+        assert!(local(&[String::from("x")], &mut sh_vars).is_err());
+
+        sh_vars.push_scope();
+        local(&[String::from("x=inner")], &mut sh_vars).unwrap();
+        assert_eq!(sh_vars.get("x"), Some(&String::from("inner")));
+
+        local(&[String::from("y=")], &mut sh_vars).unwrap();
+        assert_eq!(sh_vars.get("y"), Some(&String::new()));
+
+        sh_vars.pop_scope();
+        assert_eq!(sh_vars.get("x"), Some(&String::from("outer")));
+    }
+}
@@ -83,7 +83,11 @@ impl Error {
 }
 
 impl core::fmt::Display for Error {
+    /// Errors of kind `Other` are shown as their message alone, e.g. "cannot open out.txt: permission denied".
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        core::write!(f, "{}: {}", self.kind.as_str(), self.message)
+        match self.kind {
+            ErrorKind::Other => core::write!(f, "{}", self.message),
+            _ => core::write!(f, "{}: {}", self.kind.as_str(), self.message),
+        }
     }
 }
@@ -1,41 +1,116 @@
-use std::{fs, io, process};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, PipeReader, PipeWriter, Read, Write},
+    mem,
+    path::Path,
+    process, thread,
+};
 
-use std::os::unix::{io::IntoRawFd, process::CommandExt};
+use std::os::unix::{
+    io::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+    process::{CommandExt, ExitStatusExt},
+};
 
 use crate::{
-    ast::{expression::*, statement::*},
+    ast::{arithmetic::*, expression::*, statement::*},
     builtin,
     error::*,
+    manifest,
     result::*,
-    sh_vars::ShVars,
-    state::State,
+    sh_vars::{ShVars, Value},
+    state::{ControlFlow, State},
+    utils::{
+        self,
+        brace::{self, Piece},
+        glob::GlobOptions,
+    },
 };
 
-fn execute_assignment(assignment: Assignment, sh_vars: &mut ShVars) -> Result<()> {
+fn execute_assignment(
+    assignment: Assignment,
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<()> {
     let identifier = match assignment.identifier() {
         Expression::Identifier(identifier) => identifier.to_string(),
         _ => Err(Error::NOT_IMPLEMENTED)?,
     };
 
+    if is_special_parameter(&identifier) {
+        Err(Error::new(
+            ErrorKind::Other,
+            format!("${identifier}: cannot assign to a special parameter"),
+        ))?
+    }
+
+    // assigning a list or a map variable copies it as a whole.
+    let collection = match assignment.value() {
+        Expression::Identifier(name) => sh_vars
+            .get_value(name)
+            .filter(|value| matches!(value, Value::List(_) | Value::Map(_)))
+            .cloned(),
+        _ => None,
+    };
+
+    if let Some(value) = collection {
+        return sh_vars.insert_value(identifier, value);
+    }
+
     let value = match assignment.value() {
-        Expression::Null => String::default(),
-        Expression::String(string) => string.to_string(),
-        Expression::Boolean(boolean) => boolean.to_string(),
-        Expression::Number(number) => number.to_string(),
-        Expression::FileDescriptor(filedescriptor) => filedescriptor.to_string(),
-        _ => Err(Error::NOT_IMPLEMENTED)?,
+        Expression::List(items) => {
+            let items = expand_arguments(items, state, sh_vars)?;
+
+            return sh_vars.insert_value(identifier, Value::List(items));
+        }
+        Expression::Map(entries) => {
+            let mut map = BTreeMap::new();
+
+            for (key, value) in entries {
+                map.insert(key.to_string(), expand_expression(value, state, sh_vars)?);
+            }
+
+            return sh_vars.insert_value(identifier, Value::Map(map));
+        }
+        value => expand_expression(&expand_tilde(value, state, sh_vars), state, sh_vars)?,
     };
 
-    sh_vars.insert(identifier, value)
+    // a literal value is stored by `ShVars::insert`, while an expanded one is kept as it is,
+    // even when empty (e.g., the output of `$(git branch --show-current)`).
+    match assignment.value() {
+        Expression::Null | Expression::String(_) => sh_vars.insert(identifier, value),
+        _ => sh_vars.insert_value(identifier, Value::String(value)),
+    }
 }
 
-fn execute_builtin_command(name: &String, args: &Vec<String>, state: &mut State) -> Result<()> {
+/// Executes a builtin command and returns its exit status.
+///
+/// The output of the listing builtins (`export`, `env` and `set` without arguments)
+/// is written to the command's standard output.
+///
+/// Returns `Err(Error::NOT_IMPLEMENTED)` if `name` is not a builtin or the builtin fails silently.
+fn execute_builtin_command(
+    name: &String,
+    args: &Vec<String>,
+    redirects: &[Redirect],
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<i32> {
     match name.as_str() {
         "cd" => {
-            builtin::cd(
-                args.get(0).unwrap_or(&String::from("/")),
-                state.current_dir(),
-            )?;
+            let path = args.get(0).map_or("/", String::as_str);
+
+            // a failing `cd` is an error of the builtin, not a reason to look for a `cd` command.
+            let current_dir = builtin::cd(path, state.current_dir()).map_err(|_| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("cd: {path}: cannot change directory"),
+                )
+            })?;
+
+            let previous_dir = mem::replace(state.current_dir_mut(), current_dir);
+
+            *state.previous_dir_mut() = Some(previous_dir);
         }
 
         "abort" => {
@@ -43,20 +118,136 @@ fn execute_builtin_command(name: &String, args: &Vec<String>, state: &mut State)
         }
 
         "exit" => {
-            builtin::exit(
-                args.get(0)
-                    .unwrap_or(&String::from("0"))
-                    .parse::<i32>()
-                    .unwrap_or(0),
-            );
+            // without an argument, the status is the one of the last command.
+            // as in other shells, an invalid status is reported and the shell still exits.
+            let exit_status = match args.get(0).map(|arg| (arg, arg.parse::<i32>())) {
+                Some((_, Ok(exit_status))) => exit_status,
+                Some((arg, Err(_))) => {
+                    eprintln!("fsh: exit: {arg}: numeric argument required");
+
+                    2
+                }
+                None => state.exit_status(),
+            };
+
+            // in a subshell, only the subshell is left.
+            if state.subshell_depth() > 0 {
+                *state.control_flow_mut() = Some(ControlFlow::Exit);
+
+                return Ok(exit_status);
+            }
+
+            builtin::exit(exit_status);
+        }
+
+        "set" if args.is_empty() => {
+            write_builtin_output(builtin::variables(sh_vars), redirects, state)?;
+        }
+
+        "set" => {
+            // `set -- a b c` replaces the positional arguments.
+            match args.iter().position(|arg| arg == "--") {
+                Some(index) => {
+                    builtin::set(&args[..index], state.options_mut())?;
+
+                    *state.arguments_mut() = args[index + 1..].to_vec();
+                }
+                None => builtin::set(args, state.options_mut())?,
+            }
+        }
+
+        "shift" => {
+            return builtin::shift(args, state.arguments_mut());
+        }
+
+        "export" => {
+            let lines = builtin::export(args, sh_vars)?;
+
+            write_builtin_output(lines, redirects, state)?;
+        }
+
+        "unset" => {
+            builtin::unset(args, sh_vars)?;
+        }
+
+        "local" => {
+            builtin::local(args, sh_vars)?;
+        }
+
+        // with arguments, `env` runs a command and is left to the external one.
+        "env" if args.is_empty() => {
+            write_builtin_output(builtin::env(sh_vars), redirects, state)?;
+        }
+
+        "test" => {
+            return Ok(execute_test(args, state));
+        }
+
+        "[" => {
+            return Ok(match args.split_last() {
+                Some((last, args)) if last == "]" => execute_test(args, state),
+                _ => {
+                    eprintln!("fsh: [: missing `]`");
+
+                    2
+                }
+            });
         }
 
         _ => Err(Error::NOT_IMPLEMENTED)?,
     }
 
+    Ok(0)
+}
+
+/// Evaluates the expression of `test` (or `[`), and returns its exit status.
+///
+/// As in other shells, a malformed expression is reported on stderr with the status 2,
+/// so that the caller (e.g., the condition of `if`) can act on it.
+fn execute_test(args: &[String], state: &State) -> i32 {
+    match builtin::test(args, state.current_dir()) {
+        Ok(result) => !result as i32,
+        Err(err) => {
+            eprintln!("fsh: {err}");
+
+            2
+        }
+    }
+}
+
+/// Writes the output lines of a builtin to its standard output: the target of its
+/// (or an enclosing group's) redirect, the enclosing pipe, or the shell's standard output.
+fn write_builtin_output(
+    lines: Vec<String>,
+    redirects: &[Redirect],
+    state: &mut State,
+) -> Result<()> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let mut fds =
+        open_redirects(redirects.to_vec(), state).inspect_err(|_| *state.exit_status_mut() = 1)?;
+
+    let mut writer: Box<dyn Write> = match (fds.remove(&1), &state.pipe().1) {
+        (Some(owned_fd), _) => Box::new(fs::File::from(owned_fd)),
+        (None, Some(pipe)) => Box::new(pipe.try_clone().map_err(|_| Error::NOT_IMPLEMENTED)?),
+        (None, None) => Box::new(io::stdout()),
+    };
+
+    for line in lines {
+        writeln!(writer, "{line}")
+            .map_err(|err| Error::new(ErrorKind::Other, describe_io_error(&err)))?;
+    }
+
     Ok(())
 }
 
+/// Spawns an external command with its redirects.
+///
+/// Redirect targets are opened and the child's file descriptors are prepared here in the shell,
+/// so that a failure is reported as an error, and only the placement of descriptors above
+/// stderr runs in the child before `exec` (see `spawn_with_fds`).
 fn execute_process_command(
     name: String,
     args: Vec<String>,
@@ -64,96 +255,211 @@ fn execute_process_command(
     is_background: bool,
     state: &mut State,
     sh_vars: &mut ShVars,
-    is_last: bool,
-) -> io::Result<()> {
-    let mut ps_command = process::Command::new(name);
+) -> Result<()> {
+    let mut ps_command = process::Command::new(&name);
 
     ps_command.args(args);
 
-    if let Some(ref pipe) = state.pipe().0 {
-        ps_command.stdin(pipe.try_clone().unwrap());
-    } else {
-        ps_command.stdin(process::Stdio::inherit());
-    }
+    // only the exported variables are passed to the child.
+    ps_command.env_clear();
 
-    if let Some(ref pipe) = state.pipe().1 {
-        ps_command.stdout(pipe.try_clone().unwrap());
-    } else {
-        ps_command.stdout(process::Stdio::inherit());
+    ps_command.envs(sh_vars.environment());
+
+    ps_command.current_dir(state.current_dir());
+
+    let fds = open_redirects(redirects, state).inspect_err(|_| *state.exit_status_mut() = 1)?;
+
+    let mut extra_fds = Vec::new();
+
+    let mut stdio = [None, None, None];
+
+    for (fd, owned_fd) in fds {
+        match stdio.get_mut(fd as usize) {
+            Some(slot) => *slot = Some(process::Stdio::from(owned_fd)),
+            None => extra_fds.push((fd, owned_fd)),
+        }
     }
 
-    ps_command.stderr(process::Stdio::inherit());
+    let [stdin, stdout, stderr] = stdio;
+
+    match (stdin, &state.pipe().0) {
+        (Some(stdin), _) => ps_command.stdin(stdin),
+        (None, Some(pipe)) => ps_command.stdin(pipe.try_clone().unwrap()),
+        (None, None) => ps_command.stdin(process::Stdio::inherit()),
+    };
+
+    match (stdout, &state.pipe().1) {
+        (Some(stdout), _) => ps_command.stdout(stdout),
+        (None, Some(pipe)) => ps_command.stdout(pipe.try_clone().unwrap()),
+        (None, None) => ps_command.stdout(process::Stdio::inherit()),
+    };
 
-    ps_command.envs(sh_vars.entries());
+    ps_command.stderr(stderr.unwrap_or(process::Stdio::inherit()));
 
-    ps_command.current_dir(state.current_dir());
+    // as in other shells, a command that is not found has the status 127,
+    // and one that cannot be run otherwise has the status 126.
+    let child = spawn_with_fds(&mut ps_command, extra_fds).map_err(|err| {
+        let (exit_status, message) = match err.kind() {
+            io::ErrorKind::NotFound if !name.contains('/') => {
+                (127, format!("{name}: command not found"))
+            }
+            io::ErrorKind::NotFound => (127, format!("{name}: {}", describe_io_error(&err))),
+            _ => (126, format!("{name}: {}", describe_io_error(&err))),
+        };
+
+        *state.exit_status_mut() = exit_status;
+
+        Error::new(ErrorKind::Other, message)
+    })?;
 
-    if is_last {
-        ps_command.stdout(process::Stdio::inherit());
+    if is_background {
+        *state.last_background_pid_mut() = Some(child.id());
     }
 
-    unsafe {
-        let sh_vars_cloned = sh_vars.clone();
-        ps_command.pre_exec(move || {
-            for redirect in &redirects {
-                let left = match redirect.left() {
-                    Expression::FileDescriptor(fd) => *fd,
+    state.processes_mut().push((child, is_background));
 
-                    _ => Err(create_stdio_invalid_fd_error())?,
-                };
+    Ok(())
+}
 
-                let right = match redirect.right() {
-                    &Expression::String(ref string) => {
-                        match fs::File::options()
-                            .create(true)
-                            .read(true)
-                            .write(true)
-                            .open(string)
-                        {
-                            Ok(file) => file.into_raw_fd(),
-                            Err(_) => Err(create_stdio_invalid_fd_error())?,
-                        }
-                    }
-                    &Expression::Identifier(ref identifier) => {
-                        let string = match sh_vars_cloned.get(identifier) {
-                            None => Err(create_stdio_invalid_fd_error())?,
-                            Some(string) => string,
-                        };
-
-                        match fs::File::options()
-                            .create(true)
-                            .read(true)
-                            .write(true)
-                            .open(string)
-                        {
-                            Ok(file) => file.into_raw_fd(),
-                            Err(_) => Err(create_stdio_invalid_fd_error())?,
-                        }
-                    }
+/// Opens the targets of redirects and returns the file descriptors of the child, by number.
+///
+/// The descriptors redirected for the enclosing groups (`State::fds`) are included.
+/// Redirects are applied in order, so `@2 > @1` refers to the descriptor that `@1` is
+/// redirected to at that point. The text of here-documents and here-strings is written
+/// to a pipe on a separate thread, and the read end is used as the descriptor.
+///
+/// # Returns
+/// - `Ok(BTreeMap<i32, OwnedFd>)` with the redirected descriptors.
+/// - `Err(Error)` if a target cannot be opened, e.g. "cannot open out.txt: permission denied".
+fn open_redirects(redirects: Vec<Redirect>, state: &State) -> Result<BTreeMap<i32, OwnedFd>> {
+    let is_noclobber = state.options().contains("noclobber");
 
-                    &Expression::Number(number) => {
-                        match fs::File::options()
-                            .create(true)
-                            .read(true)
-                            .write(true)
-                            .open(number.to_string())
-                        {
-                            Ok(file) => file.into_raw_fd(),
-                            Err(_) => Err(create_stdio_invalid_fd_error())?,
-                        }
-                    }
+    let mut fds = clone_fds(state.fds())?;
+
+    for redirect in redirects {
+        let left = match redirect.left() {
+            Expression::FileDescriptor(fd) => *fd,
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        };
+
+        let right = match (redirect.operator(), redirect.right()) {
+            (
+                RedirectOperator::HereDocument | RedirectOperator::HereString,
+                Expression::String(text),
+            ) => {
+                let (reader, mut writer) = io::pipe().map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+                let text = text.to_string();
+
+                thread::spawn(move || writer.write_all(text.as_bytes()));
+
+                OwnedFd::from(reader)
+            }
+
+            (_, Expression::String(path)) if path.is_empty() => {
+                Err(Error::new(ErrorKind::Other, "ambiguous redirect"))?
+            }
+
+            (operator, Expression::String(path)) => {
+                let file =
+                    open_redirect_file(state.current_dir().join(path), operator, is_noclobber)
+                        .map_err(|err| {
+                            Error::new(
+                                ErrorKind::Other,
+                                format!("cannot open {path}: {}", describe_io_error(&err)),
+                            )
+                        })?;
 
-                    &Expression::FileDescriptor(fd) => fd,
-                    _ => Err(create_stdio_invalid_path_error())?,
+                OwnedFd::from(file)
+            }
+
+            (_, Expression::FileDescriptor(fd)) => {
+                let duplicated = match fds.get(fd) {
+                    Some(owned_fd) => OwnedFd::try_clone(owned_fd),
+                    None if *fd == 0 && state.pipe().0.is_some() => state
+                        .pipe()
+                        .0
+                        .as_ref()
+                        .unwrap()
+                        .try_clone()
+                        .map(OwnedFd::from),
+                    None if *fd == 1 && state.pipe().1.is_some() => state
+                        .pipe()
+                        .1
+                        .as_ref()
+                        .unwrap()
+                        .try_clone()
+                        .map(OwnedFd::from),
+                    // the child inherits the descriptor from the shell.
+                    None if *fd >= 0 => unsafe { BorrowedFd::borrow_raw(*fd) }.try_clone_to_owned(),
+                    None => Err(io::Error::from_raw_os_error(libc::EBADF)),
                 };
 
-                match redirect.operator() {
-                    RedirectOperator::GreaterThan => {
-                        redirection(right, left).unwrap();
-                    }
-                    RedirectOperator::LessThan => {
-                        redirection(right, left).unwrap();
-                    }
+                duplicated.map_err(|err| {
+                    Error::new(
+                        ErrorKind::Other,
+                        format!("@{fd}: {}", describe_io_error(&err)),
+                    )
+                })?
+            }
+
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        };
+
+        fds.insert(left, right);
+    }
+
+    Ok(fds)
+}
+
+/// Duplicates the file descriptors of a map, by number.
+fn clone_fds(fds: &BTreeMap<i32, OwnedFd>) -> Result<BTreeMap<i32, OwnedFd>> {
+    fds.iter()
+        .map(|(fd, owned_fd)| owned_fd.try_clone().map(|owned_fd| (*fd, owned_fd)))
+        .collect::<io::Result<_>>()
+        .map_err(|_| Error::NOT_IMPLEMENTED)
+}
+
+/// Spawns the command with additional file descriptors (above stderr) open in the child.
+///
+/// `process::Command` only sets up the standard streams, so the descriptors are duplicated
+/// above every target number with close-on-exec, and placed at their numbers in the child
+/// between `fork` and `exec`. The shell's own descriptors are left as they are, since other
+/// threads (e.g., of a process substitution) may be opening files at the same time.
+fn spawn_with_fds(
+    ps_command: &mut process::Command,
+    fds: Vec<(i32, OwnedFd)>,
+) -> io::Result<process::Child> {
+    if fds.is_empty() {
+        return ps_command.spawn();
+    }
+
+    // copies above every target, so that placing one descriptor cannot overwrite another.
+    let lowest = fds.iter().map(|(fd, _)| fd + 1).max().unwrap_or(0);
+
+    let sources = fds
+        .iter()
+        .map(|(_, owned_fd)| {
+            match unsafe { libc::fcntl(owned_fd.as_raw_fd(), libc::F_DUPFD_CLOEXEC, lowest) } {
+                -1 => Err(io::Error::last_os_error()),
+                fd => Ok(unsafe { OwnedFd::from_raw_fd(fd) }),
+            }
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let placements = fds
+        .iter()
+        .zip(&sources)
+        .map(|((fd, _), source)| (*fd, source.as_raw_fd()))
+        .collect::<Vec<_>>();
+
+    // only `dup2` runs in the child, which is async-signal-safe, and it clears close-on-exec
+    // on the target. The copies themselves are closed by `exec`.
+    unsafe {
+        ps_command.pre_exec(move || {
+            for &(fd, source) in &placements {
+                if libc::dup2(source, fd) == -1 {
+                    return Err(io::Error::last_os_error());
                 }
             }
 
@@ -161,185 +467,1815 @@ fn execute_process_command(
         });
     }
 
-    let child = ps_command.spawn()?;
+    // the copies are closed in the shell once the child is spawned.
+    let child = ps_command.spawn();
 
-    state.processes_mut().push((child, is_background));
+    drop(sources);
 
-    // if let Some(child) = state.handler().get(pid) {
-    //     child
-    // }
+    child
+}
 
-    Ok(())
+/// Describes an I/O error in the style of a shell message, e.g. "permission denied".
+fn describe_io_error(err: &io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::NotFound => String::from("no such file or directory"),
+        io::ErrorKind::PermissionDenied => String::from("permission denied"),
+        io::ErrorKind::IsADirectory => String::from("is a directory"),
+        io::ErrorKind::NotADirectory => String::from("not a directory"),
+        io::ErrorKind::AlreadyExists => String::from("cannot overwrite existing file"),
+        _ if err.raw_os_error() == Some(libc::EBADF) => String::from("bad file descriptor"),
+        _ => err.to_string().to_lowercase(),
+    }
 }
 
-fn execute_command(
-    command: Command,
-    state: &mut State,
-    sh_vars: &mut ShVars,
-    is_last: bool,
-) -> Result<()> {
+/// Executes a command, after replacing its process substitutions with `/dev/fd/N` paths.
+///
+/// The shell's ends of the substitution pipes are open in `State::fds` while the command starts,
+/// and closed afterwards, so that the substitutions see the end of their input (or output).
+fn execute_command(command: Command, state: &mut State, sh_vars: &mut ShVars) -> Result<()> {
+    let mut fds = Vec::new();
+
+    let mut arguments = Vec::with_capacity(command.arguments().len());
+
+    for argument in command.arguments() {
+        arguments.push(start_process_substitution(
+            argument, &mut fds, state, sh_vars,
+        )?);
+    }
+
+    let mut redirects = Vec::with_capacity(command.redirects().len());
+
+    for redirect in command.redirects() {
+        let right = start_process_substitution(redirect.right(), &mut fds, state, sh_vars)?;
+
+        redirects.push(Redirect::new(
+            redirect.operator().clone(),
+            redirect.left().clone(),
+            right,
+        ));
+    }
+
+    let command = Command::new(
+        command.assignments().clone(),
+        command.name().clone(),
+        arguments,
+        redirects,
+        command.is_background().clone(),
+    );
+
+    let numbers = fds.iter().map(|(fd, _)| *fd).collect::<Vec<_>>();
+
+    state.fds_mut().extend(fds);
+
+    let result = dispatch_command(command, state, sh_vars);
+
+    for fd in numbers {
+        state.fds_mut().remove(&fd);
+    }
+
+    result
+}
+
+/// Runs a command as a brace group, a subshell, a function, a builtin or an external process.
+///
+/// The assignments before the command name are exported for the duration of the command
+/// in a scope of their own, so the shell's variables are restored afterwards.
+fn dispatch_command(command: Command, state: &mut State, sh_vars: &mut ShVars) -> Result<()> {
     let name = match command.name() {
         Expression::String(string) => string.to_owned(),
 
-        Expression::Identifier(identifier) => match sh_vars.get(identifier) {
-            None => Err(Error::NOT_IMPLEMENTED)?,
-            Some(string) => string,
+        Expression::Identifier(identifier) => {
+            lookup(identifier, state, sh_vars).ok_or(Error::NOT_IMPLEMENTED)?
         }
-        .to_string(),
 
         Expression::Number(number) => number.to_string(),
 
+        Expression::Boolean(boolean) => boolean.to_string(),
+
+        Expression::Substitution(statement) => {
+            execute_substitution((**statement).clone(), state, sh_vars)?
+        }
+
+        Expression::Parameter(parameter) => expand_parameter(parameter, state, sh_vars)?,
+
+        Expression::Word(parts) => expand_word(parts, state, sh_vars)?,
+
+        Expression::Group(_) | Expression::Subshell(_) => {
+            return execute_group(command, state, sh_vars);
+        }
+
         _ => Err(Error::NOT_IMPLEMENTED)?,
     };
 
-    let mut arguments = Vec::with_capacity(command.arguments().len());
+    let arguments = expand_arguments(command.arguments(), state, sh_vars)?;
 
-    for argument in command.arguments() {
-        let argument = match &argument {
-            Expression::String(string) => {
-                let mut string_vec = globbing(&string);
-                if string_vec.len() > 0 {
-                    arguments.append(&mut string_vec);
+    let mut assignments = Vec::with_capacity(command.assignments().len());
 
-                    continue;
-                } else {
-                    string
-                }
-            }
-            Expression::Number(number) => &number.to_owned().to_string(),
-            Expression::Identifier(identifier) => &sh_vars
-                .get(identifier)
-                .unwrap_or(&String::default())
-                .to_string(),
+    for assignment in command.assignments() {
+        let identifier = match assignment.identifier() {
+            Expression::Identifier(identifier) => identifier.to_string(),
             _ => Err(Error::NOT_IMPLEMENTED)?,
         };
 
-        arguments.push(argument.to_string());
+        let value = expand_tilde(assignment.value(), state, sh_vars);
+
+        assignments.push((identifier, expand_expression(&value, state, sh_vars)?));
+    }
+
+    if assignments.is_empty() {
+        return run_command(name, arguments, &command, state, sh_vars);
+    }
+
+    sh_vars.push_scope();
+
+    let result = assignments
+        .into_iter()
+        .try_for_each(|(identifier, value)| {
+            sh_vars.declare_local(&identifier)?;
+
+            sh_vars.insert_value(&identifier, Value::String(value))?;
+
+            sh_vars.export(identifier)
+        })
+        .and_then(|_| run_command(name, arguments, &command, state, sh_vars));
+
+    sh_vars.pop_scope();
+
+    result
+}
+
+/// Runs a command whose name and arguments are expanded as a function,
+/// a builtin or an external process.
+fn run_command(
+    name: String,
+    arguments: Vec<String>,
+    command: &Command,
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<()> {
+    if let Some(body) = state.functions().get(&name).cloned() {
+        return execute_function(body, arguments, state, sh_vars);
     }
 
-    let redirects = command.redirects().to_vec();
+    let redirects = expand_redirects(command.redirects(), state, sh_vars)?;
 
     let is_background = match command.is_background() {
         &Expression::Boolean(boolean) => boolean,
         _ => false,
     };
 
-    execute_builtin_command(&name, &arguments, state).or_else(|_| {
-        execute_process_command(
-            name,
-            arguments,
-            redirects,
-            is_background,
-            state,
-            sh_vars,
-            is_last,
-        )
-        .map_err(|_| Error::NOT_IMPLEMENTED)
-    })
-}
+    match execute_builtin_command(&name, &arguments, &redirects, state, sh_vars) {
+        Ok(exit_status) => {
+            *state.exit_status_mut() = exit_status;
 
-pub fn execute(ast: Statement, state: &mut State, sh_vars: &mut ShVars) -> Result<()> {
-    match ast {
-        Statement::Sequence(mut sequence) => {
-            while let Some(ast) = sequence.pop_front() {
-                execute(ast, state, sh_vars)?;
-            }
+            return Ok(());
         }
 
-        Statement::Assignment(assignment) => {
-            execute_assignment(assignment, sh_vars)?;
+        Err(err) if err != Error::NOT_IMPLEMENTED => {
+            *state.exit_status_mut() = 2;
+
+            return Err(err);
         }
 
-        // ToDo
-        Statement::Redirect(_) => todo!(),
+        Err(_) => {}
+    }
 
-        Statement::Command(command) => {
-            execute_command(command, state, sh_vars, true)?;
+    execute_process_command(name, arguments, redirects, is_background, state, sh_vars)
+}
 
-            if let Some(mut ps) = state.processes_mut().pop() {
-                if ps.1 == false {
-                    ps.0.wait().unwrap();
-                }
+/// Expands the targets of redirects to paths (or the text of here-documents),
+/// while file descriptors are kept. A leading tilde of a path is expanded as well.
+fn expand_redirects(
+    redirects: &[Redirect],
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<Vec<Redirect>> {
+    let mut expanded = Vec::with_capacity(redirects.len());
+
+    for redirect in redirects {
+        let right = match (redirect.operator(), redirect.right()) {
+            (_, Expression::FileDescriptor(fd)) => Expression::FileDescriptor(*fd),
+            (RedirectOperator::HereDocument, right) => {
+                Expression::String(expand_expression(right, state, sh_vars)?)
             }
-        }
+            (RedirectOperator::HereString, right) => {
+                let right = expand_tilde(right, state, sh_vars);
 
-        Statement::Pipe(mut pipe) => {
-            let mut prev_r = None;
+                Expression::String(expand_expression(&right, state, sh_vars)? + "\n")
+            }
+            (_, right) => {
+                let right = expand_tilde(right, state, sh_vars);
 
-            while let Some(command) = pipe.pop_front() {
-                let is_last = pipe.is_empty();
+                Expression::String(expand_expression(&right, state, sh_vars)?)
+            }
+        };
 
-                let (r, mut w) = if is_last {
-                    (None, None)
-                } else {
-                    let (r, w) = std::io::pipe().map_err(|_| Error::NOT_IMPLEMENTED)?;
-                    (Some(r), Some(w))
-                };
+        expanded.push(Redirect::new(
+            redirect.operator().clone(),
+            redirect.left().clone(),
+            right,
+        ));
+    }
 
-                state.pipe_mut().0 = prev_r.take();
-                state.pipe_mut().1 = w.take();
+    Ok(expanded)
+}
 
-                execute_command(command, state, sh_vars, pipe.is_empty())?;
+/// Executes a brace group or a subshell with its redirects.
+///
+/// The redirects apply to every command of the group: `@0` and `@1` replace the ends of
+/// `State::pipe`, and the other descriptors are added to `State::fds` while the group runs.
+/// A group run in the background is executed as a subshell on a separate thread.
+fn execute_group(command: Command, state: &mut State, sh_vars: &mut ShVars) -> Result<()> {
+    let (body, is_subshell) = match command.name() {
+        Expression::Group(body) => ((**body).clone(), false),
+        Expression::Subshell(body) => ((**body).clone(), true),
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
 
-                prev_r = r;
+    let redirects = expand_redirects(command.redirects(), state, sh_vars)?;
 
-                drop(w);
-            }
+    let mut fds = open_redirects(redirects, state).inspect_err(|_| *state.exit_status_mut() = 1)?;
 
-            *state.pipe_mut() = (None, None);
+    let reader = match fds.remove(&0) {
+        Some(owned_fd) => Some(PipeReader::from(owned_fd)),
+        None => state
+            .pipe()
+            .0
+            .as_ref()
+            .map(|r| r.try_clone())
+            .transpose()
+            .map_err(|_| Error::NOT_IMPLEMENTED)?,
+    };
 
-            while let Some(mut ps) = state.processes_mut().pop() {
-                if ps.1 == false {
-                    ps.0.wait().unwrap();
-                }
-            }
-        }
-    }
+    let writer = match fds.remove(&1) {
+        Some(owned_fd) => Some(PipeWriter::from(owned_fd)),
+        None => state
+            .pipe()
+            .1
+            .as_ref()
+            .map(|w| w.try_clone())
+            .transpose()
+            .map_err(|_| Error::NOT_IMPLEMENTED)?,
+    };
 
-    Ok(())
-}
+    if command.is_background() == &Expression::Boolean(true) {
+        let handle = spawn_subshell(body, (reader, writer), fds, state, sh_vars);
 
-fn create_stdio_invalid_fd_error() -> io::Error {
-    std::io::Error::new(io::ErrorKind::Other, "invalid file descriptor")
-}
+        add_background_job(handle, state);
 
-fn create_stdio_invalid_path_error() -> io::Error {
-    std::io::Error::new(io::ErrorKind::Other, "invalid file path")
-}
+        *state.exit_status_mut() = 0;
 
-fn redirection(left: i32, right: i32) -> Result<()> {
-    unsafe {
-        if libc::dup2(left, right) >= 0 {
-            Ok(())
-        } else {
-            Err(Error::NOT_IMPLEMENTED)
-        }
+        return Ok(());
     }
-}
 
-pub fn globbing(path: &str) -> Vec<String> {
-    if path.is_empty() {
-        return Vec::new();
-    }
+    let pipe = mem::replace(state.pipe_mut(), (reader, writer));
 
-    let mut v = Vec::new();
+    let outer_fds = mem::replace(state.fds_mut(), fds);
 
-    glob::glob(&path)
-        .map(|paths| {
-            paths
-                .map(|path| path.unwrap_or_default().to_string_lossy().to_string())
-                .collect::<Vec<String>>()
-        })
-        .map(|paths| {
-            if paths.len() > 0 {
-                for path in paths {
-                    v.push(path);
-                }
-            } else {
-                v.push(path.to_string());
-            }
-        })
-        .unwrap_or_else(|_| v.push(path.to_string()));
+    // the commands of the group are waited for within it, apart from the enclosing pipe.
+    let processes = mem::take(state.processes_mut());
 
-    v
+    let result = if is_subshell {
+        execute_subshell(body, state, sh_vars)
+    } else {
+        execute_block(body, state, sh_vars)
+    };
+
+    *state.processes_mut() = processes;
+
+    *state.fds_mut() = outer_fds;
+
+    *state.pipe_mut() = pipe;
+
+    result
+}
+
+/// Executes a user-defined function with the given positional arguments.
+///
+/// The caller's positional arguments and loop depth are restored afterwards,
+/// and a pending `return` is consumed here.
+fn execute_function(
+    body: Statement,
+    arguments: Vec<String>,
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<()> {
+    let arguments = mem::replace(state.arguments_mut(), arguments);
+
+    let loop_depth = mem::take(state.loop_depth_mut());
+
+    *state.function_depth_mut() += 1;
+
+    let result = execute_block(body, state, sh_vars);
+
+    *state.function_depth_mut() -= 1;
+
+    *state.loop_depth_mut() = loop_depth;
+
+    *state.arguments_mut() = arguments;
+
+    if state.control_flow() == Some(ControlFlow::Return) {
+        *state.control_flow_mut() = None;
+    }
+
+    result
+}
+
+/// Runs a statement as a subshell on a separate thread, with the given pipe and file descriptors.
+///
+/// The subshell starts with copies of the shell variables, the working directory, functions,
+/// positional arguments, special parameters and options. Its errors are reported on stderr.
+///
+/// # Returns
+/// The handle of the thread, which returns the exit status of the subshell.
+fn spawn_subshell(
+    body: Statement,
+    pipe: (Option<PipeReader>, Option<PipeWriter>),
+    fds: BTreeMap<i32, OwnedFd>,
+    state: &State,
+    sh_vars: &ShVars,
+) -> thread::JoinHandle<i32> {
+    let mut subshell = State::from(state.current_dir());
+
+    *subshell.pipe_mut() = pipe;
+    *subshell.fds_mut() = fds;
+    *subshell.previous_dir_mut() = state.previous_dir().map(Path::to_path_buf);
+    *subshell.functions_mut() = state.functions().clone();
+    *subshell.arguments_mut() = state.arguments().clone();
+    *subshell.script_name_mut() = state.script_name().to_string();
+    *subshell.last_background_pid_mut() = state.last_background_pid();
+    *subshell.exit_status_mut() = state.exit_status();
+    *subshell.options_mut() = state.options().clone();
+    *subshell.subshell_depth_mut() = 1;
+
+    let mut sh_vars = sh_vars.clone();
+
+    thread::spawn(move || {
+        if let Err(err) = execute(body, &mut subshell, &mut sh_vars) {
+            eprintln!("fsh: {err}");
+
+            if subshell.exit_status() == 0 {
+                *subshell.exit_status_mut() = 1;
+            }
+        }
+
+        subshell.exit_status()
+    })
+}
+
+/// Starts a process substitution and returns the `/dev/fd/N` path that replaces it.
+/// Any other expression is returned as it is.
+///
+/// The statement runs as a subshell (see `spawn_subshell`) connected to a pipe, whose other end
+/// is added to `fds` at its own number. The subshell is added to `State::process_substitutions`.
+fn start_process_substitution(
+    expression: &Expression,
+    fds: &mut Vec<(i32, OwnedFd)>,
+    state: &mut State,
+    sh_vars: &ShVars,
+) -> Result<Expression> {
+    let (statement, is_input) = match expression {
+        Expression::InputSubstitution(statement) => ((**statement).clone(), true),
+        Expression::OutputSubstitution(statement) => ((**statement).clone(), false),
+        expression => return Ok(expression.clone()),
+    };
+
+    let (reader, writer) = io::pipe().map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+    let (pipe, owned_fd) = if is_input {
+        ((None, Some(writer)), OwnedFd::from(reader))
+    } else {
+        ((Some(reader), None), OwnedFd::from(writer))
+    };
+
+    let handle = spawn_subshell(statement, pipe, clone_fds(state.fds())?, state, sh_vars);
+
+    state.process_substitutions_mut().push(handle);
+
+    let fd = owned_fd.as_raw_fd();
+
+    fds.push((fd, owned_fd));
+
+    Ok(Expression::String(format!("/dev/fd/{fd}")))
+}
+
+/// Waits for the process substitutions started after the first `count`, once their command
+/// has finished. Those of a background command are left running.
+fn finish_process_substitutions(count: usize, is_background: bool, state: &mut State) {
+    for handle in state.process_substitutions_mut().split_off(count) {
+        if !is_background {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Keeps the thread of a background pipe or group with the running background jobs.
+///
+/// The jobs that have already finished are dropped.
+fn add_background_job(handle: thread::JoinHandle<i32>, state: &mut State) {
+    state
+        .background_jobs_mut()
+        .retain(|handle| !handle.is_finished());
+
+    state.background_jobs_mut().push(handle);
+}
+
+/// Executes a statement in a subshell.
+///
+/// The statement runs on a copy of the shell variables, and the working directories, functions,
+/// positional arguments and options are restored afterwards. A pending `exit` (or any other
+/// control flow request) ends with the subshell.
+fn execute_subshell(body: Statement, state: &mut State, sh_vars: &ShVars) -> Result<()> {
+    let mut sh_vars = sh_vars.clone();
+
+    let current_dir = state.current_dir().to_path_buf();
+
+    let previous_dir = state.previous_dir().map(Path::to_path_buf);
+
+    let functions = state.functions().clone();
+
+    let arguments = state.arguments().clone();
+
+    let options = state.options().clone();
+
+    *state.subshell_depth_mut() += 1;
+
+    let result = execute(body, state, &mut sh_vars);
+
+    *state.subshell_depth_mut() -= 1;
+
+    *state.control_flow_mut() = None;
+
+    *state.options_mut() = options;
+
+    *state.arguments_mut() = arguments;
+
+    *state.functions_mut() = functions;
+
+    *state.previous_dir_mut() = previous_dir;
+
+    // `cd` also changes the working directory of the process.
+    if state.current_dir() != current_dir {
+        env::set_current_dir(&current_dir).map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+        *state.current_dir_mut() = current_dir;
+    }
+
+    result
+}
+
+/// Executes a command substitution and returns its output.
+///
+/// The statement runs as a subshell (see `execute_subshell`), so its assignments, `cd` and `exit`
+/// do not affect the shell, with its standard output connected to the write end of
+/// `State::pipe`. The read end is drained on a separate thread so that a large
+/// output cannot fill the pipe and block the command. Trailing newlines are removed.
+fn execute_substitution(
+    statement: Statement,
+    state: &mut State,
+    sh_vars: &ShVars,
+) -> Result<String> {
+    let (mut reader, writer) = io::pipe().map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+    let handle = thread::spawn(move || {
+        let mut buffer = Vec::new();
+
+        reader.read_to_end(&mut buffer).map(|_| buffer)
+    });
+
+    let pipe = mem::replace(state.pipe_mut(), (None, Some(writer)));
+
+    let result = execute_subshell(statement, state, sh_vars);
+
+    // dropping the write end lets the reader see the end of the output.
+    drop(mem::replace(state.pipe_mut(), pipe));
+
+    let buffer = handle
+        .join()
+        .map_err(|_| Error::NOT_IMPLEMENTED)?
+        .map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+    result?;
+
+    Ok(String::from_utf8_lossy(&buffer)
+        .trim_end_matches('\n')
+        .to_string())
+}
+
+/// Expands a scalar expression into a single string, without globbing.
+///
+/// Returns `Err(Error::NOT_IMPLEMENTED)` for lists, maps, process substitutions and groups.
+fn expand_expression(
+    expression: &Expression,
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<String> {
+    match expression {
+        Expression::Null => Ok(String::default()),
+        Expression::String(string) | Expression::Quoted(string) => Ok(string.to_string()),
+        Expression::Boolean(boolean) => Ok(boolean.to_string()),
+        Expression::Number(number) => Ok(number.to_string()),
+        Expression::FileDescriptor(filedescriptor) => Ok(filedescriptor.to_string()),
+        Expression::Substitution(statement) => {
+            execute_substitution((**statement).clone(), state, sh_vars)
+        }
+        Expression::Identifier(identifier) => {
+            Ok(lookup(identifier, state, sh_vars).unwrap_or_default())
+        }
+        Expression::Index(identifier, accessor) => {
+            Ok(expand_index(identifier, accessor, state, sh_vars))
+        }
+        Expression::Arithmetic(arithmetic) => {
+            Ok(evaluate_arithmetic(arithmetic, state, sh_vars)?.to_string())
+        }
+        Expression::Parameter(parameter) => expand_parameter(parameter, state, sh_vars),
+        Expression::Word(parts) => expand_word(parts, state, sh_vars),
+        Expression::List(_)
+        | Expression::Map(_)
+        | Expression::InputSubstitution(_)
+        | Expression::OutputSubstitution(_)
+        | Expression::Group(_)
+        | Expression::Subshell(_) => Err(Error::NOT_IMPLEMENTED),
+    }
+}
+
+/// Looks up the value of an identifier.
+///
+/// The special parameters are computed from the state:
+/// - `$?`: the exit status of the last command.
+/// - `$$`: the process ID of the shell.
+/// - `$!`: the process ID of the last background command.
+/// - `$0`: the name of the script or the shell.
+/// - `$1`, `$2`, ...: the positional arguments, `$#` their count and `$@` all of them.
+/// - `$FSH_VERSION`: the version of fsh.
+///
+/// Everything else is read from the shell variables. Lists and maps are joined with spaces.
+fn lookup(identifier: &str, state: &State, sh_vars: &ShVars) -> Option<String> {
+    match identifier {
+        "?" => return Some(state.exit_status().to_string()),
+        "$" => return Some(process::id().to_string()),
+        "!" => return state.last_background_pid().map(|pid| pid.to_string()),
+        "0" => return Some(state.script_name().to_string()),
+        "#" => return Some(state.arguments().len().to_string()),
+        "@" => return Some(state.arguments().join(" ")),
+        "FSH_VERSION" => return Some(manifest::MANIFEST_FSH_VERSION.to_string()),
+        _ => {}
+    }
+
+    match identifier.parse::<usize>() {
+        Ok(index) if index > 0 => state.arguments().get(index - 1).cloned(),
+        _ => sh_vars.get_value(identifier).map(|value| value.to_string()),
+    }
+}
+
+/// Returns `true` for the read-only special parameters (`$?`, `$$`, `$!`, `$#`, `$@` and `$0`)
+/// and the positional arguments (`$1`, `$2`, ...).
+fn is_special_parameter(name: &str) -> bool {
+    matches!(name, "?" | "$" | "!" | "#" | "@" | "0") || name.parse::<usize>().is_ok()
+}
+
+/// Expands an index into a list (`$paths[0]`) or a map (`$cfg.key` or `$cfg[key]`).
+///
+/// Negative list indexes count from the end, and a missing item or key expands to an empty string.
+/// If the variable is not a list or a map, the accessor is kept as written (e.g., `$file.bak`).
+fn expand_index(identifier: &str, accessor: &str, state: &State, sh_vars: &ShVars) -> String {
+    let key = accessor
+        .strip_prefix('.')
+        .or_else(|| accessor.strip_prefix('[')?.strip_suffix(']'))
+        .unwrap_or(accessor);
+
+    match sh_vars.get_value(identifier) {
+        Some(Value::List(items)) if accessor.starts_with('[') => match key.parse::<isize>() {
+            Ok(index) => {
+                let index = match index < 0 {
+                    true => items.len().checked_sub(index.unsigned_abs()),
+                    false => Some(index as usize),
+                };
+
+                index
+                    .and_then(|index| items.get(index))
+                    .cloned()
+                    .unwrap_or_default()
+            }
+            Err(_) => String::default(),
+        },
+
+        Some(Value::Map(map)) => map.get(key).cloned().unwrap_or_default(),
+
+        _ => format!(
+            "{}{accessor}",
+            lookup(identifier, state, sh_vars).unwrap_or_default()
+        ),
+    }
+}
+
+/// Expands the parts of a word and joins them into a single string.
+///
+/// Identifiers are replaced by their values (or an empty string if unset),
+/// and command substitutions by their output.
+fn expand_word(parts: &[Expression], state: &mut State, sh_vars: &mut ShVars) -> Result<String> {
+    let mut word = String::new();
+
+    for part in parts {
+        match part {
+            Expression::String(string) | Expression::Quoted(string) => word.push_str(string),
+            Expression::Identifier(identifier) => {
+                word.push_str(&lookup(identifier, state, sh_vars).unwrap_or_default())
+            }
+            Expression::Index(identifier, accessor) => {
+                word.push_str(&expand_index(identifier, accessor, state, sh_vars))
+            }
+            Expression::Substitution(statement) => word.push_str(&execute_substitution(
+                (**statement).clone(),
+                state,
+                sh_vars,
+            )?),
+            Expression::Parameter(parameter) => {
+                word.push_str(&expand_parameter(parameter, state, sh_vars)?)
+            }
+            Expression::Arithmetic(arithmetic) => {
+                word.push_str(&evaluate_arithmetic(arithmetic, state, sh_vars)?.to_string())
+            }
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        }
+    }
+
+    Ok(word)
+}
+
+/// Expands the parts of a word into its text and a shell pattern matching it.
+///
+/// Only the unquoted literal text of the word keeps its special characters in the pattern;
+/// quoted text and the results of expansions are escaped, so they match literally.
+///
+/// # Returns
+/// - The expanded text and the pattern.
+fn expand_word_pattern(
+    parts: &[Expression],
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<(String, String)> {
+    let mut word = String::new();
+
+    let mut pattern = String::new();
+
+    for part in parts {
+        let text = expand_word(std::slice::from_ref(part), state, sh_vars)?;
+
+        match part {
+            Expression::String(_) => pattern.push_str(&text),
+            _ => pattern.push_str(&utils::glob::escape(&text)),
+        }
+
+        word.push_str(&text);
+    }
+
+    Ok((word, pattern))
+}
+
+/// Returns `true` if the unquoted literal text of a word contains a glob character.
+fn is_glob_word(parts: &[Expression]) -> bool {
+    parts.iter().any(
+        |part| matches!(part, Expression::String(string) if utils::glob::has_glob_chars(string)),
+    )
+}
+
+/// Expands a braced parameter expansion (e.g., `${HOME:-/root}`) into its value.
+///
+/// The word of an operator is only expanded when it is used.
+fn expand_parameter(
+    parameter: &Parameter,
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<String> {
+    let name = parameter.name();
+
+    // an index into a list or a map (e.g., `${PIPESTATUS[1]}`) is kept in the name.
+    let value = match name.split_once('[') {
+        Some((identifier, index)) => Some(expand_index(
+            identifier,
+            &format!("[{index}"),
+            state,
+            sh_vars,
+        )),
+        None => lookup(name, state, sh_vars),
+    };
+
+    // the colon forms (e.g., `:-`) also treat an empty value as unset.
+    let is_set = |is_null_checked: bool| {
+        value
+            .as_ref()
+            .is_some_and(|v| !is_null_checked || !v.is_empty())
+    };
+
+    match parameter.operator() {
+        ParameterOperator::Value => Ok(value.unwrap_or_default()),
+
+        ParameterOperator::Length => match sh_vars.get_value(name) {
+            Some(Value::List(items)) => Ok(items.len().to_string()),
+            Some(Value::Map(map)) => Ok(map.len().to_string()),
+            _ => Ok(value.unwrap_or_default().chars().count().to_string()),
+        },
+
+        ParameterOperator::Default {
+            word,
+            is_null_checked,
+        } => match is_set(*is_null_checked) {
+            true => Ok(value.unwrap_or_default()),
+            false => expand_word(word, state, sh_vars),
+        },
+
+        ParameterOperator::Assign {
+            word,
+            is_null_checked,
+        } => {
+            if is_set(*is_null_checked) {
+                return Ok(value.unwrap_or_default());
+            }
+
+            if is_special_parameter(name) || name.contains('[') {
+                Err(Error::new(
+                    ErrorKind::Other,
+                    format!("${name}: cannot assign in this way"),
+                ))?
+            }
+
+            let value = expand_word(word, state, sh_vars)?;
+
+            sh_vars.insert(name, value.as_str())?;
+
+            Ok(value)
+        }
+
+        ParameterOperator::Error {
+            word,
+            is_null_checked,
+        } => {
+            if is_set(*is_null_checked) {
+                return Ok(value.unwrap_or_default());
+            }
+
+            let message = expand_word(word, state, sh_vars)?;
+
+            let message = match message.is_empty() {
+                true => "parameter null or not set",
+                false => message.as_str(),
+            };
+
+            Err(Error::new(ErrorKind::Other, format!("{name}: {message}")))
+        }
+
+        ParameterOperator::Alternative {
+            word,
+            is_null_checked,
+        } => match is_set(*is_null_checked) {
+            true => expand_word(word, state, sh_vars),
+            false => Ok(String::new()),
+        },
+
+        ParameterOperator::Substring { offset, length } => {
+            let chars = value.unwrap_or_default().chars().collect::<Vec<_>>();
+
+            let len = chars.len() as isize;
+
+            // negative positions count from the end of the value.
+            let start = if *offset < 0 { len + offset } else { *offset }.clamp(0, len);
+
+            let end = match length {
+                Some(length) if *length < 0 => len + length,
+                Some(length) => start.saturating_add(*length),
+                None => len,
+            }
+            .clamp(start, len);
+
+            Ok(chars[start as usize..end as usize].iter().collect())
+        }
+
+        ParameterOperator::RemovePrefix {
+            pattern,
+            is_longest,
+        } => {
+            let value = value.unwrap_or_default();
+
+            let (_, pattern) = expand_word_pattern(pattern, state, sh_vars)?;
+
+            let pattern = compile_pattern(&pattern)?;
+
+            let mut ends = char_boundaries(&value);
+
+            if *is_longest {
+                ends.reverse();
+            }
+
+            let end = ends.into_iter().find(|end| pattern.matches(&value[..*end]));
+
+            Ok(value[end.unwrap_or(0)..].to_string())
+        }
+
+        ParameterOperator::RemoveSuffix {
+            pattern,
+            is_longest,
+        } => {
+            let value = value.unwrap_or_default();
+
+            let (_, pattern) = expand_word_pattern(pattern, state, sh_vars)?;
+
+            let pattern = compile_pattern(&pattern)?;
+
+            let mut starts = char_boundaries(&value);
+
+            if !*is_longest {
+                starts.reverse();
+            }
+
+            let start = starts
+                .into_iter()
+                .find(|start| pattern.matches(&value[*start..]));
+
+            Ok(value[..start.unwrap_or(value.len())].to_string())
+        }
+    }
+}
+
+/// Evaluates an arithmetic expression.
+///
+/// Unset or empty variables count as `0`. Overflow and division by zero are reported as errors.
+fn evaluate_arithmetic(
+    arithmetic: &Arithmetic,
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<isize> {
+    let overflow = || Error::new(ErrorKind::Other, "arithmetic overflow");
+
+    match arithmetic {
+        Arithmetic::Number(number) => Ok(*number),
+
+        Arithmetic::Variable(variable) => {
+            parse_arithmetic_value(&lookup(variable, state, sh_vars).unwrap_or_default())
+        }
+
+        Arithmetic::Expansion(expression) => {
+            let value = match &**expression {
+                Expression::Parameter(parameter) => expand_parameter(parameter, state, sh_vars)?,
+                Expression::Index(identifier, accessor) => {
+                    expand_index(identifier, accessor, state, sh_vars)
+                }
+                Expression::Substitution(statement) => {
+                    execute_substitution((**statement).clone(), state, sh_vars)?
+                }
+                Expression::Arithmetic(arithmetic) => {
+                    return evaluate_arithmetic(arithmetic, state, sh_vars);
+                }
+                _ => Err(Error::NOT_IMPLEMENTED)?,
+            };
+
+            parse_arithmetic_value(&value)
+        }
+
+        Arithmetic::Unary(operator, operand) => {
+            let operand = evaluate_arithmetic(operand, state, sh_vars)?;
+
+            match operator {
+                ArithmeticOperator::Add => Ok(operand),
+                ArithmeticOperator::Subtract => operand.checked_neg().ok_or_else(overflow),
+                ArithmeticOperator::Not => Ok((operand == 0) as isize),
+                _ => Err(Error::NOT_IMPLEMENTED),
+            }
+        }
+
+        Arithmetic::Binary(operator, left, right) => {
+            let left = evaluate_arithmetic(left, state, sh_vars)?;
+
+            let right = evaluate_arithmetic(right, state, sh_vars)?;
+
+            if matches!(
+                operator,
+                ArithmeticOperator::Divide | ArithmeticOperator::Remainder
+            ) && right == 0
+            {
+                Err(Error::new(ErrorKind::Other, "division by zero"))?
+            }
+
+            match operator {
+                ArithmeticOperator::Add => left.checked_add(right).ok_or_else(overflow),
+                ArithmeticOperator::Subtract => left.checked_sub(right).ok_or_else(overflow),
+                ArithmeticOperator::Multiply => left.checked_mul(right).ok_or_else(overflow),
+                ArithmeticOperator::Divide => left.checked_div(right).ok_or_else(overflow),
+                ArithmeticOperator::Remainder => left.checked_rem(right).ok_or_else(overflow),
+                ArithmeticOperator::Power => {
+                    let exponent = u32::try_from(right)
+                        .map_err(|_| Error::new(ErrorKind::Other, "exponent less than 0"))?;
+
+                    left.checked_pow(exponent).ok_or_else(overflow)
+                }
+                ArithmeticOperator::Equal => Ok((left == right) as isize),
+                ArithmeticOperator::NotEqual => Ok((left != right) as isize),
+                ArithmeticOperator::LessThan => Ok((left < right) as isize),
+                ArithmeticOperator::LessThanOrEqual => Ok((left <= right) as isize),
+                ArithmeticOperator::GreaterThan => Ok((left > right) as isize),
+                ArithmeticOperator::GreaterThanOrEqual => Ok((left >= right) as isize),
+                ArithmeticOperator::Not => Err(Error::NOT_IMPLEMENTED),
+            }
+        }
+    }
+}
+
+/// Parses the value of a variable or an expansion used in an arithmetic expression.
+///
+/// An empty value counts as `0`.
+fn parse_arithmetic_value(value: &str) -> Result<isize> {
+    let value = value.trim();
+
+    if value.is_empty() {
+        return Ok(0);
+    }
+
+    value
+        .parse::<isize>()
+        .map_err(|_| Error::new(ErrorKind::Other, format!("{value}: not a number")))
+}
+
+/// Returns the byte offsets of the character boundaries of a string, including its end.
+fn char_boundaries(string: &str) -> Vec<usize> {
+    string
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([string.len()])
+        .collect()
+}
+
+/// Compiles a shell pattern (e.g., `*.txt`) used by the pattern removal operators.
+fn compile_pattern(pattern: &str) -> Result<glob::Pattern> {
+    glob::Pattern::new(pattern)
+        .map_err(|_| Error::new(ErrorKind::Other, format!("{pattern}: invalid pattern")))
+}
+
+/// Expands argument expressions into the words passed to a command.
+///
+/// Unquoted strings and words are globbed, identifiers are replaced by their values
+/// (or an empty string if unset).
+/// A list variable expands into one argument per item, and a map variable into one per value.
+fn expand_arguments(
+    expressions: &[Expression],
+    state: &mut State,
+    sh_vars: &mut ShVars,
+) -> Result<Vec<String>> {
+    let mut arguments = Vec::with_capacity(expressions.len());
+
+    for argument in expand_braces(expressions) {
+        let argument = expand_tilde(&argument, state, sh_vars);
+
+        let argument = match &argument {
+            Expression::String(_) | Expression::Word(_) => {
+                let parts = match &argument {
+                    Expression::Word(parts) => parts.as_slice(),
+                    string => std::slice::from_ref(string),
+                };
+
+                let (word, pattern) = expand_word_pattern(parts, state, sh_vars)?;
+
+                match is_glob_word(parts) {
+                    true => arguments.extend(globbing(&pattern, word, state)?),
+                    false => arguments.push(word),
+                }
+
+                continue;
+            }
+            Expression::Number(number) => &number.to_owned().to_string(),
+            Expression::Boolean(boolean) => &boolean.to_string(),
+            // `$@` expands into one argument per positional argument.
+            Expression::Identifier(identifier) if identifier == "@" => {
+                arguments.extend(state.arguments().iter().cloned());
+
+                continue;
+            }
+            Expression::Identifier(identifier) => match sh_vars.get_value(identifier) {
+                Some(Value::List(items)) => {
+                    arguments.extend(items.iter().cloned());
+
+                    continue;
+                }
+                Some(Value::Map(map)) => {
+                    arguments.extend(map.values().cloned());
+
+                    continue;
+                }
+                _ => &lookup(identifier, state, sh_vars).unwrap_or_default(),
+            },
+            Expression::Index(identifier, accessor) => {
+                &expand_index(identifier, accessor, state, sh_vars)
+            }
+            Expression::Substitution(statement) => {
+                &execute_substitution((**statement).clone(), state, sh_vars)?
+            }
+            Expression::Parameter(parameter) => &expand_parameter(parameter, state, sh_vars)?,
+            Expression::Arithmetic(arithmetic) => {
+                &evaluate_arithmetic(arithmetic, state, sh_vars)?.to_string()
+            }
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        };
+
+        arguments.push(argument.to_string());
+    }
+
+    Ok(arguments)
+}
+
+/// Expands the braces of strings and words (e.g., `file.{rs,toml}` or `img{1..10}.png`),
+/// before any other expansion. The expansions in a word are kept as they are, so `{$a,b}`
+/// becomes `$a` and `b`.
+fn expand_braces(expressions: &[Expression]) -> Vec<Expression> {
+    let mut expanded = Vec::with_capacity(expressions.len());
+
+    for expression in expressions {
+        let word = match expression {
+            Expression::String(string) if string.contains('{') => {
+                string.chars().map(Piece::Char).collect::<Vec<_>>()
+            }
+            Expression::Word(parts) => parts
+                .iter()
+                .flat_map(|part| match part {
+                    Expression::String(string) => string.chars().map(Piece::Char).collect(),
+                    part => vec![Piece::Opaque(part.clone())],
+                })
+                .collect(),
+            expression => {
+                expanded.push(expression.clone());
+                continue;
+            }
+        };
+
+        for word in brace::expand_braces(&word) {
+            let mut parts: Vec<Expression> = Vec::new();
+
+            for piece in word {
+                match (piece, parts.last_mut()) {
+                    (Piece::Char(c), Some(Expression::String(string))) => string.push(c),
+                    (Piece::Char(c), _) => parts.push(Expression::String(c.to_string())),
+                    (Piece::Opaque(part), _) => parts.push(part),
+                }
+            }
+
+            expanded.push(match parts.len() {
+                0 => Expression::String(String::new()),
+                1 if matches!(parts[0], Expression::String(_)) => parts.remove(0),
+                _ => Expression::Word(parts),
+            });
+        }
+    }
+
+    expanded
+}
+
+/// Expands the tilde prefix of a string, or of a word whose prefix ends in its first part
+/// (e.g., `~/$dir`, but not `~$user`). See `expand_tilde_prefix`.
+fn expand_tilde(expression: &Expression, state: &State, sh_vars: &ShVars) -> Expression {
+    match expression {
+        Expression::String(string) => {
+            Expression::String(expand_tilde_prefix(string, state, sh_vars))
+        }
+        Expression::Word(parts) => match parts.split_first() {
+            Some((Expression::String(first), rest)) if first.contains('/') => {
+                let mut parts = vec![Expression::String(expand_tilde_prefix(
+                    first, state, sh_vars,
+                ))];
+
+                parts.extend_from_slice(rest);
+
+                Expression::Word(parts)
+            }
+            _ => expression.clone(),
+        },
+        expression => expression.clone(),
+    }
+}
+
+/// Expands the tilde prefix of a word, up to the first `/`.
+///
+/// `~` is the value of `HOME`, `~+` the working directory, `~-` the previous working directory,
+/// and `~user` the home directory of `user`. A word without a tilde prefix, or whose prefix
+/// cannot be expanded (e.g., an unknown user), is returned as it is.
+fn expand_tilde_prefix(word: &str, state: &State, sh_vars: &ShVars) -> String {
+    let Some(rest) = word.strip_prefix('~') else {
+        return word.to_string();
+    };
+
+    let (prefix, suffix) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+
+    let dir = match (prefix, sh_vars.get("HOME")) {
+        ("", Some(home)) => home.to_string(),
+        ("+", _) => state.current_dir().to_string_lossy().to_string(),
+        ("-", _) => match state.previous_dir() {
+            Some(previous_dir) => previous_dir.to_string_lossy().to_string(),
+            None => return word.to_string(),
+        },
+        _ => {
+            return utils::path::expand_tilde_to_home_dir(word)
+                .to_string_lossy()
+                .to_string()
+        }
+    };
+
+    format!("{dir}{suffix}")
+}
+
+/// Executes one iteration of a loop body and consumes a pending `break` or `continue`.
+///
+/// A pending `return` (or `exit`) is left in place for the enclosing function (or subshell).
+///
+/// # Returns
+/// - `Ok(true)` if the loop should go on with the next iteration.
+/// - `Ok(false)` if the loop should stop.
+fn execute_loop_body(body: Statement, state: &mut State, sh_vars: &mut ShVars) -> Result<bool> {
+    execute_block(body, state, sh_vars)?;
+
+    match state.control_flow() {
+        Some(ControlFlow::Break) => {
+            *state.control_flow_mut() = None;
+            Ok(false)
+        }
+
+        Some(ControlFlow::Continue) => {
+            *state.control_flow_mut() = None;
+            Ok(true)
+        }
+
+        Some(ControlFlow::Return | ControlFlow::Exit) => Ok(false),
+
+        None => Ok(true),
+    }
+}
+
+fn execute_while(while_statement: While, state: &mut State, sh_vars: &mut ShVars) -> Result<()> {
+    let mut exit_status = 0;
+
+    loop {
+        execute(while_statement.condition().clone(), state, sh_vars)?;
+
+        if state.exit_status() != 0 {
+            break;
+        }
+
+        let is_continue = execute_loop_body(while_statement.body().clone(), state, sh_vars)?;
+
+        exit_status = state.exit_status();
+
+        if !is_continue {
+            break;
+        }
+    }
+
+    *state.exit_status_mut() = exit_status;
+
+    Ok(())
+}
+
+fn execute_for(for_statement: For, state: &mut State, sh_vars: &mut ShVars) -> Result<()> {
+    let identifier = match for_statement.identifier() {
+        Expression::Identifier(identifier) => identifier.to_string(),
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    let items = expand_arguments(for_statement.items(), state, sh_vars)?;
+
+    *state.exit_status_mut() = 0;
+
+    for item in items {
+        sh_vars.insert(&identifier, item)?;
+
+        if !execute_loop_body(for_statement.body().clone(), state, sh_vars)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Executes the body of a function, a brace group, a loop or a branch in a new scope,
+/// so that the variables declared `local` in it are restored when it ends.
+fn execute_block(body: Statement, state: &mut State, sh_vars: &mut ShVars) -> Result<()> {
+    sh_vars.push_scope();
+
+    let result = execute(body, state, sh_vars);
+
+    sh_vars.pop_scope();
+
+    result
+}
+
+pub fn execute(ast: Statement, state: &mut State, sh_vars: &mut ShVars) -> Result<()> {
+    match ast {
+        Statement::Sequence(mut sequence) => {
+            // as in other shells, a failing command is reported and the next ones go on.
+            while let Some(ast) = sequence.pop_front() {
+                execute_checked(ast, state, sh_vars);
+
+                if state.control_flow().is_some() {
+                    break;
+                }
+            }
+        }
+
+        Statement::Assignment(assignment) => {
+            execute_assignment(assignment, state, sh_vars)?;
+        }
+
+        // ToDo
+        Statement::Redirect(_) => todo!(),
+
+        Statement::Command(command) => {
+            let count = state.process_substitutions().len();
+
+            let is_background = command.is_background() == &Expression::Boolean(true);
+
+            let result = execute_command(command, state, sh_vars).and_then(|_| {
+                if let Some(mut ps) = state.processes_mut().pop() {
+                    *state.exit_status_mut() = if ps.1 == false { wait(&mut ps.0)? } else { 0 };
+                }
+
+                Ok(())
+            });
+
+            finish_process_substitutions(count, is_background, state);
+
+            set_pipe_status(&[state.exit_status()], sh_vars)?;
+
+            result?;
+        }
+
+        Statement::Pipe(mut pipe) => {
+            // an enclosing pipe (e.g., of a command substitution) feeds the first command
+            // and receives the output of the last one.
+            let outer = mem::take(state.pipe_mut());
+
+            let mut prev_r = outer
+                .0
+                .as_ref()
+                .map(|r| r.try_clone())
+                .transpose()
+                .map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+            let is_background = pipe
+                .back()
+                .is_some_and(|command| command.is_background() == &Expression::Boolean(true));
+
+            // the commands before the last one, each running as a subshell on its own thread.
+            let mut stages = Vec::new();
+
+            let first_process = state.processes().len();
+
+            let count = state.process_substitutions().len();
+
+            while let Some(command) = pipe.pop_front() {
+                let is_last = pipe.is_empty();
+
+                let (r, mut w) = if is_last {
+                    let w = outer
+                        .1
+                        .as_ref()
+                        .map(|w| w.try_clone())
+                        .transpose()
+                        .map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+                    (None, w)
+                } else {
+                    let (r, w) = std::io::pipe().map_err(|_| Error::NOT_IMPLEMENTED)?;
+                    (Some(r), Some(w))
+                };
+
+                // a command that runs in the shell (e.g., a brace group or a function) must run
+                // along with the next ones, or it blocks once its output fills the pipe.
+                if !is_last {
+                    let fds = clone_fds(state.fds())?;
+
+                    stages.push(spawn_subshell(
+                        Statement::Command(command),
+                        (prev_r.take(), w.take()),
+                        fds,
+                        state,
+                        sh_vars,
+                    ));
+
+                    prev_r = r;
+
+                    continue;
+                }
+
+                state.pipe_mut().0 = prev_r.take();
+                state.pipe_mut().1 = w.take();
+
+                *state.exit_status_mut() = 0;
+
+                // as in a separate process, a failing command (e.g., one that is not found)
+                // is reported and the rest of the pipe goes on.
+                if let Err(err) = execute_command(command, state, sh_vars) {
+                    eprintln!("fsh: {err}");
+
+                    if state.exit_status() == 0 {
+                        *state.exit_status_mut() = 1;
+                    }
+                }
+            }
+
+            *state.pipe_mut() = outer;
+
+            // a background pipe keeps its commands running, and the shell goes on at once.
+            let mut statuses = match is_background {
+                true => {
+                    stages
+                        .into_iter()
+                        .for_each(|handle| add_background_job(handle, state));
+
+                    Vec::new()
+                }
+                false => stages
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap_or(1))
+                    .collect::<Vec<_>>(),
+            };
+
+            let mut exit_status = state.exit_status();
+
+            for (mut child, is_background) in state.processes_mut().split_off(first_process) {
+                exit_status = match is_background {
+                    true => 0,
+                    false => wait(&mut child)?,
+                };
+            }
+
+            statuses.push(exit_status);
+
+            finish_process_substitutions(count, is_background, state);
+
+            // with `pipefail`, the pipe fails with the last command that failed.
+            let exit_status = match state.options().contains("pipefail") {
+                true => statuses.iter().rev().find(|status| **status != 0),
+                false => statuses.last(),
+            };
+
+            *state.exit_status_mut() = exit_status.copied().unwrap_or_default();
+
+            set_pipe_status(&statuses, sh_vars)?;
+        }
+
+        Statement::Logical(logical) => {
+            execute_checked(logical.left().clone(), state, sh_vars);
+
+            let is_success = state.exit_status() == 0;
+
+            let is_continue = match logical.operator() {
+                LogicalOperator::And => is_success,
+                LogicalOperator::Or => !is_success,
+            };
+
+            if is_continue && state.control_flow().is_none() {
+                execute(logical.right().clone(), state, sh_vars)?;
+            }
+        }
+
+        Statement::If(if_statement) => {
+            execute_checked(if_statement.condition().clone(), state, sh_vars);
+
+            if state.exit_status() == 0 {
+                execute_block(if_statement.consequence().clone(), state, sh_vars)?;
+            } else if let Some(alternative) = if_statement.alternative() {
+                execute_block(alternative.clone(), state, sh_vars)?;
+            } else {
+                *state.exit_status_mut() = 0;
+            }
+        }
+
+        Statement::While(while_statement) => {
+            *state.loop_depth_mut() += 1;
+
+            let result = execute_while(while_statement, state, sh_vars);
+
+            *state.loop_depth_mut() -= 1;
+
+            result?;
+        }
+
+        Statement::For(for_statement) => {
+            *state.loop_depth_mut() += 1;
+
+            let result = execute_for(for_statement, state, sh_vars);
+
+            *state.loop_depth_mut() -= 1;
+
+            result?;
+        }
+
+        Statement::Break => {
+            if state.loop_depth() == 0 {
+                Err(Error::new(
+                    ErrorKind::Other,
+                    "break: only meaningful in a loop",
+                ))?
+            }
+
+            *state.control_flow_mut() = Some(ControlFlow::Break);
+        }
+
+        Statement::Continue => {
+            if state.loop_depth() == 0 {
+                Err(Error::new(
+                    ErrorKind::Other,
+                    "continue: only meaningful in a loop",
+                ))?
+            }
+
+            *state.control_flow_mut() = Some(ControlFlow::Continue);
+        }
+
+        Statement::Function(function) => {
+            let name = match function.name() {
+                Expression::String(string) => string.to_string(),
+                _ => Err(Error::NOT_IMPLEMENTED)?,
+            };
+
+            state.functions_mut().insert(name, function.body().clone());
+
+            *state.exit_status_mut() = 0;
+        }
+
+        Statement::Return(status) => {
+            if state.function_depth() == 0 {
+                Err(Error::new(
+                    ErrorKind::Other,
+                    "return: only meaningful in a function",
+                ))?
+            }
+
+            if let Some(status) = status {
+                *state.exit_status_mut() = match status {
+                    Expression::Number(number) => number as i32,
+                    Expression::Identifier(identifier) => lookup(&identifier, state, sh_vars)
+                        .and_then(|string| string.parse::<i32>().ok())
+                        .ok_or(Error::new(
+                            ErrorKind::Other,
+                            format!("return: {identifier}: numeric argument required"),
+                        ))?,
+                    _ => Err(Error::NOT_IMPLEMENTED)?,
+                };
+            }
+
+            *state.control_flow_mut() = Some(ControlFlow::Return);
+        }
+    }
+
+    Ok(())
+}
+
+/// Executes a statement whose exit status is checked, such as the left side of `&&` and `||`,
+/// the condition of `if` or a statement of a sequence.
+///
+/// An error (e.g., a command that is not found or a redirect that cannot be opened) is reported
+/// on stderr and kept as a non-zero exit status, so that the caller can act on it.
+fn execute_checked(statement: Statement, state: &mut State, sh_vars: &mut ShVars) {
+    if let Err(err) = execute(statement, state, sh_vars) {
+        eprintln!("fsh: {err}");
+
+        if state.exit_status() == 0 {
+            *state.exit_status_mut() = 1;
+        }
+    }
+}
+
+/// Records the exit statuses of the commands of the last pipe, in order, as the `PIPESTATUS` list.
+/// A single command is recorded as a pipe of one command.
+fn set_pipe_status(statuses: &[i32], sh_vars: &mut ShVars) -> Result<()> {
+    let statuses = statuses.iter().map(|status| status.to_string()).collect();
+
+    sh_vars.insert_value("PIPESTATUS", Value::List(statuses))
+}
+
+/// Waits for the child process to exit and returns its exit status.
+///
+/// A process terminated by a signal has the status 128 plus the signal number, and the signal
+/// is reported on stderr (e.g., "Segmentation fault (core dumped)"), except for `SIGINT`
+/// and `SIGPIPE`.
+fn wait(child: &mut process::Child) -> Result<i32> {
+    let status = child.wait().map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+    if let Some(code) = status.code() {
+        return Ok(code);
+    }
+
+    let signal = status.signal().unwrap_or_default();
+
+    if signal != libc::SIGINT && signal != libc::SIGPIPE {
+        let mut message = describe_signal(signal);
+
+        if status.core_dumped() {
+            message.push_str(" (core dumped)");
+        }
+
+        eprintln!("{message}");
+    }
+
+    Ok(128 + signal)
+}
+
+/// Returns the description of a signal (e.g., "Segmentation fault" for `SIGSEGV`).
+fn describe_signal(signal: i32) -> String {
+    // SAFETY: `strsignal` returns a pointer to a NUL-terminated string, or null.
+    let description = unsafe { libc::strsignal(signal) };
+
+    match description.is_null() {
+        true => format!("Signal {signal}"),
+        // SAFETY: the pointer is not null and points to a NUL-terminated string.
+        false => unsafe { std::ffi::CStr::from_ptr(description) }
+            .to_string_lossy()
+            .into_owned(),
+    }
+}
+
+/// Opens the target file of a redirect with the mode of its operator.
+///
+/// - `>` truncates or creates the file. With `is_noclobber`, an existing regular file is an error.
+/// - `>|` truncates or creates the file, even with `is_noclobber`.
+/// - `>>` appends to or creates the file.
+/// - `<` opens an existing file for reading.
+/// - `<>` opens or creates the file for reading and writing.
+fn open_redirect_file(
+    path: impl AsRef<std::path::Path>,
+    operator: &RedirectOperator,
+    is_noclobber: bool,
+) -> io::Result<fs::File> {
+    let path = path.as_ref();
+
+    let mut options = fs::File::options();
+
+    match operator {
+        // other files, such as `/dev/null`, may still be written to.
+        RedirectOperator::GreaterThan if is_noclobber => match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "cannot overwrite existing file",
+            ))?,
+            Ok(_) => options.write(true),
+            Err(_) => options.write(true).create_new(true),
+        },
+
+        RedirectOperator::GreaterThan | RedirectOperator::GreaterThanPipe => {
+            options.write(true).create(true).truncate(true)
+        }
+
+        RedirectOperator::DoubleGreaterThan => options.append(true).create(true),
+
+        RedirectOperator::LessThan => options.read(true),
+
+        RedirectOperator::LessThanGreaterThan => options.read(true).write(true).create(true),
+
+        RedirectOperator::HereDocument | RedirectOperator::HereString => {
+            Err(io::Error::from(io::ErrorKind::InvalidInput))?
+        }
+    };
+
+    options.open(path)
+}
+
+/// Expands a glob pattern into the sorted paths it matches, following the glob options
+/// of the shell. Paths that cannot be read are reported on stderr.
+///
+/// If nothing matches, the word is kept as it is, unless `nullglob` is set (no words)
+/// or `failglob` is set (an error).
+///
+/// # Arguments
+/// - `pattern`: The pattern, in which the quoted text of the word is escaped.
+/// - `word`: The expanded word.
+/// - `state`: The shell state holding the options and the working directory.
+pub fn globbing(pattern: &str, word: String, state: &State) -> Result<Vec<String>> {
+    let options = state.options();
+
+    let glob_options = GlobOptions {
+        is_dotglob: options.contains("dotglob"),
+        is_nocaseglob: options.contains("nocaseglob"),
+        is_globstar: options.contains("globstar"),
+    };
+
+    let (paths, errors) = utils::glob::glob(pattern, state.current_dir(), glob_options);
+
+    for err in errors {
+        eprintln!("fsh: {err}");
+    }
+
+    if !paths.is_empty() {
+        return Ok(paths);
+    }
+
+    if options.contains("failglob") {
+        Err(Error::new(ErrorKind::Other, format!("no match: {word}")))?
+    }
+
+    match options.contains("nullglob") {
+        true => Ok(Vec::new()),
+        false => Ok(vec![word]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn output(source: &str) -> String {
+        let mut state = State::from(std::env::current_dir().unwrap());
+
+        let mut sh_vars = ShVars::from(std::env::vars());
+
+        let statement = Parser::new(source).parse().unwrap();
+
+        execute_substitution(statement, &mut state, &mut sh_vars).unwrap()
+    }
+
+    fn error(source: &str) -> String {
+        let mut state = State::from(std::env::current_dir().unwrap());
+
+        let mut sh_vars = ShVars::from(std::env::vars());
+
+        let Statement::Sequence(mut sequence) = Parser::new(source).parse().unwrap() else {
+            unreachable!();
+        };
+
+        let mut result = Ok(());
+
+        // the statements are executed one by one, so that the error of the last one is kept.
+        while let Some(statement) = sequence.pop_front() {
+            result = execute(statement, &mut state, &mut sh_vars);
+        }
+
+        result.unwrap_err().to_string()
+    }
+
+    fn exit_status(source: &str) -> i32 {
+        let mut state = State::from(std::env::current_dir().unwrap());
+
+        let mut sh_vars = ShVars::new();
+
+        let statement = Parser::new(source).parse().unwrap();
+
+        execute(statement, &mut state, &mut sh_vars).unwrap();
+
+        state.exit_status()
+    }
+
+    #[test]
+    fn test_logical_after_error() {
+        assert_eq!(exit_status("fsh-no-such-command || true"), 0);
+
+        assert_eq!(exit_status("fsh-no-such-command && true"), 127);
+
+        assert_eq!(exit_status("cd /fsh-no-such-dir || true"), 0);
+
+        assert_eq!(exit_status("cd /fsh-no-such-dir && true"), 2);
+    }
+
+    #[test]
+    fn test_if_after_error() {
+        assert_eq!(
+            exit_status("if fsh-no-such-command --version { false } else { true }"),
+            0
+        );
+
+        assert_eq!(exit_status("if [ $n -gt 0 ] { false } else { true }"), 0);
+    }
+
+    #[test]
+    fn test_malformed_test() {
+        assert_eq!(exit_status("[ 1 -lt ]"), 2);
+
+        assert_eq!(exit_status("test 1 -lt"), 2);
+
+        assert_eq!(exit_status("[ 1 -lt 2"), 2);
+
+        assert_eq!(exit_status("[ 1 -lt ] || true"), 0);
+    }
+
+    #[test]
+    fn test_substitution_in_subshell() {
+        assert_eq!(output("$x = a; echo $($x = b; echo $x) $x"), "b a");
+
+        assert_eq!(output("$v = $(true); echo $v"), "");
+
+        assert_eq!(output("$v = \"$(true)\"; echo [$v]"), "[]");
+
+        assert_eq!(output("$v = $(exit 3); echo after"), "after");
+    }
+
+    #[test]
+    fn test_redirect_errors() {
+        assert_eq!(
+            error("cat < /fsh-no-such-file"),
+            "cannot open /fsh-no-such-file: no such file or directory"
+        );
+
+        assert_eq!(
+            error("echo a > /fsh-no-such-dir/out.txt"),
+            "cannot open /fsh-no-such-dir/out.txt: no such file or directory"
+        );
+
+        assert_eq!(error("echo a @1> @999"), "@999: bad file descriptor");
+
+        let path = env::temp_dir().join(format!("fsh-noclobber-{}", process::id()));
+
+        fs::write(&path, "a").unwrap();
+
+        assert_eq!(
+            error(&format!("set -C; echo b > {}", path.display())),
+            format!(
+                "cannot open {}: cannot overwrite existing file",
+                path.display()
+            )
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_redirect_above_stderr() {
+        let path = env::temp_dir().join(format!("fsh-fd-{}", process::id()));
+
+        assert_eq!(
+            output(&format!(
+                "sh -c 'echo a >&3' @3> {0}; sh -c 'cat <&4' @4< {0}",
+                path.display()
+            )),
+            "a"
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pipe_with_group() {
+        assert_eq!(output("{ seq 1 100000 } | wc -l").trim(), "100000");
+
+        assert_eq!(output("( seq 1 100000 ) | wc -l").trim(), "100000");
+
+        assert_eq!(output("seq 1 100000 | { wc -l }").trim(), "100000");
+    }
+
+    #[test]
+    fn test_pipe_with_function() {
+        assert_eq!(output("fn f { seq 1 100000 }; f | wc -l").trim(), "100000");
+    }
+
+    #[test]
+    fn test_background_pipe() {
+        let mut state = State::from(env::current_dir().unwrap());
+
+        let mut sh_vars = ShVars::from(env::vars());
+
+        let statement = Parser::new("sleep 2 | sleep 2 &").parse().unwrap();
+
+        let start = std::time::Instant::now();
+
+        execute(statement, &mut state, &mut sh_vars).unwrap();
+
+        assert!(start.elapsed().as_secs() < 1);
+        assert_eq!(state.exit_status(), 0);
+        assert_eq!(state.background_jobs().len(), 1);
+    }
+
+    #[test]
+    fn test_quoted_braces() {
+        assert_eq!(
+            output("echo \"{a,b}\" 'x{1..2}' {a,b} y{1..2}"),
+            "{a,b} x{1..2} a b y1 y2"
+        );
+
+        assert_eq!(
+            output("echo \"src/*.rs\" 'src/*.rs' \"~\" '~'"),
+            "src/*.rs src/*.rs ~ ~"
+        );
+    }
+
+    #[test]
+    fn test_sequence_after_error() {
+        assert_eq!(output("fsh-no-such-command; echo $?"), "127");
+
+        assert_eq!(output("cat < /fsh-no-such-file; echo $?"), "1");
+
+        assert_eq!(output("echo $((1 / 0)); echo after"), "after");
+    }
+
+    #[test]
+    fn test_pipe_status() {
+        assert_eq!(
+            output("true | false | true; echo $? ${PIPESTATUS[1]} $PIPESTATUS[2]"),
+            "0 1 0"
+        );
+
+        assert_eq!(output("set -o pipefail; true | false | true; echo $?"), "1");
+    }
+
+    #[test]
+    fn test_assign_special_parameter() {
+        for source in ["$? = 3", "$1 = z", "$@ = a"] {
+            assert!(error(source).ends_with("cannot assign to a special parameter"));
+        }
+
+        assert_eq!(output("$? = 3; echo $?"), "1");
+    }
 }
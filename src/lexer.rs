@@ -1,4 +1,10 @@
-use crate::{error::Error, result::Result, token::Token};
+use std::mem;
+
+use crate::{
+    error::{Error, ErrorKind},
+    result::Result,
+    token::Token,
+};
 
 /*
     0 null
@@ -11,15 +17,18 @@ const RESERVED_KEYWORDS: &[&str] = &["null", "true", "false"];
     0 ;
     1 &
     2 $
-    3 @
-    4 =
-    5 |
-    6 <
-    7 >
-    8 '
-    9 "
+    3 |
+    4 <
+    5 >
+    6 '
+    7 "
+    8 \\
 */
-const RESERVED_CHARS: &[char] = &[';', '&', '$', '@', '=', '|', '<', '>', '\'', '"'];
+// `=` and `@` are only special at the start of a word, so `--opt=value` and `user@host` are single words.
+const RESERVED_CHARS: &[char] = &[';', '&', '$', '|', '<', '>', '\'', '"', '\\'];
+
+/// The special parameters, named by a single character after `$` (e.g., `$?` or `$#`).
+const SPECIAL_PARAMETERS: &[char] = &['?', '$', '!', '#', '@'];
 
 /// A simple character-based lexer for tokenizing FSH input.
 ///
@@ -56,6 +65,11 @@ impl Lexer {
         self.source.get(self.index).copied()
     }
 
+    /// Returns the character right after the cursor, if any.
+    fn peek(&self) -> Option<char> {
+        self.source.get(self.index + 1).copied()
+    }
+
     /// Advances the cursor by one character.
     fn advance(&mut self) {
         self.index += 1;
@@ -77,317 +91,1480 @@ impl Lexer {
         self.source[start_index..self.index].iter().collect()
     }
 
-    /// Attempts to read a keyword token: `true`, `false`, or `null`.
+    /// Reads a word made of adjacent parts, e.g. `foo$BAR`, `"$HOME"/bin` or `--name='a b'`.
     ///
-    /// Resets the cursor and returns an error if no known keyword is found.
-    fn read_keyword_token(&mut self) -> Result<Token> {
+    /// A word consisting of a single part is returned as that part's token, where an
+    /// unquoted part may also be a keyword or a number. Otherwise the parts are returned
+    /// as a `Token::Word`, with adjacent literal parts merged.
+    ///
+    /// Parentheses opened within the word belong to it (e.g., `!(*.o)`), while an unmatched `)`
+    /// ends the word, so that `(cd /tmp; ls)` closes the subshell.
+    ///
+    /// Returns an error if a part is malformed (e.g., an unterminated quote).
+    fn read_word_token(&mut self) -> Result<Token> {
         let start_index = self.index;
 
-        let string = self.read_while(|c| c.is_alphabetic());
+        let mut parts: Vec<Token> = Vec::new();
 
-        // null
-        // true
-        // false
-        // _ => Err
-        match string {
-            val if val == RESERVED_KEYWORDS[0] => Ok(Token::Null),
+        let mut depth = 0usize;
 
-            val if val == RESERVED_KEYWORDS[1] => Ok(Token::Boolean(true)),
+        while let Some(c) = self.current() {
+            if c.is_whitespace() || matches!(c, ';' | '&' | '<' | '>') {
+                break;
+            }
 
-            val if val == RESERVED_KEYWORDS[2] => Ok(Token::Boolean(false)),
+            // a `|` within parentheses separates the alternatives of a pattern (e.g., `!(*.o|*.a)`).
+            if c == '|' && depth == 0 {
+                break;
+            }
 
-            _ => {
-                self.index = start_index;
+            if c == ')' && depth == 0 {
+                break;
+            }
 
-                Err(Error::NOT_IMPLEMENTED)
+            // `$name=value`: the identifier is the target of an assignment.
+            if c == '=' && matches!(parts.as_slice(), [Token::Identifier(_)]) {
+                break;
             }
-        }
-    }
 
-    /// Reads a non-quoted string token until whitespace or reserved character.
-    ///
-    /// Fails if the token is empty, numeric, or a reserved keyword.
-    fn read_string_token(&mut self) -> Result<Token> {
-        let start_index = self.index;
+            let new_parts = match c {
+                '\'' | '"' => self.read_quoted_string_token(),
 
-        let string = self.read_while(|c| !c.is_whitespace() && !RESERVED_CHARS.contains(&c));
+                '$' => self.read_expansion_token().map(|t| vec![t]),
 
-        if string.is_empty()
-            || string.parse::<isize>().is_ok()
-            || RESERVED_KEYWORDS.contains(&string.as_str())
-        {
-            self.index = start_index;
+                '\\' => self.read_escape(false).map(|s| vec![Token::Quoted(s)]),
 
-            Err(Error::NOT_IMPLEMENTED)?
-        }
+                '(' | ')' | '|' => {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
 
-        Ok(Token::String(string))
-    }
+                    self.advance();
 
-    /// Reads a quoted string token enclosed in `'` or `"` characters.
-    ///
-    /// Returns an error if quotes are unmatched or incomplete.
-    fn read_quoted_string_token(&mut self) -> Result<Token> {
-        let start_index = self.index;
+                    Ok(vec![Token::String(String::from(c))])
+                }
 
-        let quote = self.current().ok_or_else(|| Error::NOT_IMPLEMENTED)?;
+                _ => Ok(vec![Token::String(self.read_while(|c| {
+                    !c.is_whitespace() && !RESERVED_CHARS.contains(&c) && c != '(' && c != ')'
+                }))]),
+            };
 
-        if quote != '\'' && quote != '"' {
-            Err(Error::NOT_IMPLEMENTED)?
+            let new_parts = new_parts.inspect_err(|_| self.index = start_index)?;
+
+            for part in new_parts {
+                push_part(&mut parts, part);
+            }
         }
 
-        self.advance();
+        if parts.len() > 1 {
+            return Ok(Token::Word(parts));
+        }
 
-        let string = self.read_while(|c| c != quote);
+        match parts.pop() {
+            Some(Token::String(string)) => Ok(keyword_or_number(string)),
 
-        if self.current() != Some(quote) {
-            self.index = start_index;
+            Some(Token::Quoted(string)) => Ok(Token::Word(vec![Token::Quoted(string)])),
+
+            Some(part) => Ok(part),
+
+            None => Err(Error::NOT_IMPLEMENTED),
+        }
+    }
 
+    /// Reads a backslash escape sequence and returns the text it stands for.
+    ///
+    /// In bare words and inside double quotes alike, `\n`, `\t` and `\r` stand for a newline,
+    /// tab and carriage return, and `\"`, `\$` and `\\` for the escaped character.
+    /// Any other escaped character stands for itself in a bare word (e.g., `\;` or `\ `),
+    /// and keeps its backslash inside double quotes (e.g., `"a\qb"` is `a\qb`).
+    ///
+    /// Returns an error for a trailing lone backslash.
+    fn read_escape(&mut self, is_double_quoted: bool) -> Result<String> {
+        if self.current() != Some('\\') {
             Err(Error::NOT_IMPLEMENTED)?
         }
 
         self.advance();
 
-        Ok(Token::String(string))
+        let c = self.current().ok_or(Error::new(
+            ErrorKind::Other,
+            "syntax error: unexpected end of input after `\\`",
+        ))?;
+
+        self.advance();
+
+        let escaped = match c {
+            'n' => String::from('\n'),
+            't' => String::from('\t'),
+            'r' => String::from('\r'),
+            '"' | '$' | '\\' => String::from(c),
+            c if is_double_quoted => format!("\\{c}"),
+            c => String::from(c),
+        };
+
+        Ok(escaped)
     }
 
-    /// Reads a shell variable identifier (variable key) token (e.g., `$HOME`).
+    /// Reads a quoted part of a word enclosed in `'` or `"` characters.
     ///
-    /// Returns an error if the identifier is empty or malformed.
-    fn read_identifier_token(&mut self) -> Result<Token> {
+    /// Single-quoted text is kept literally, as a `Token::Quoted`. Double-quoted text is split into
+    /// quoted literal text, identifiers (`$NAME`) and command substitutions (`$(...)`),
+    /// and may contain backslash escapes.
+    ///
+    /// Returns an error if quotes are unmatched or incomplete.
+    fn read_quoted_string_token(&mut self) -> Result<Vec<Token>> {
         let start_index = self.index;
 
-        if self.current() != Some('$') {
+        let quote = self.current().ok_or(Error::NOT_IMPLEMENTED)?;
+
+        if quote != '\'' && quote != '"' {
             Err(Error::NOT_IMPLEMENTED)?
         }
 
         self.advance();
 
-        let identifier = self.read_while(|c| !c.is_whitespace() && !RESERVED_CHARS.contains(&c));
+        let mut parts = Vec::new();
 
-        if identifier.is_empty() {
-            self.index = start_index;
+        let mut string = String::new();
 
-            Err(Error::NOT_IMPLEMENTED)?
-        }
+        loop {
+            match self.current() {
+                None => {
+                    self.index = start_index;
 
-        Ok(Token::Identifier(identifier))
-    }
+                    Err(Error::NOT_IMPLEMENTED)?
+                }
 
-    /// Reads an integer literal token.
-    ///
-    /// Returns an error if the value cannot be parsed as `isize`.
-    fn read_number_token(&mut self) -> Result<Token> {
-        let start_index = self.index;
+                Some(c) if c == quote => {
+                    self.advance();
+                    break;
+                }
 
-        let string = self.read_while(|c| c.is_numeric());
+                Some('\\') if quote == '"' => {
+                    let escaped = self.read_escape(true);
 
-        string.parse::<isize>().map(Token::Number).map_err(|_| {
-            self.index = start_index;
-            Error::NOT_IMPLEMENTED
-        })
+                    string.push_str(&escaped.inspect_err(|_| self.index = start_index)?);
+                }
+
+                Some('$') if quote == '"' && self.peek().is_some_and(is_expansion_start) => {
+                    if !string.is_empty() {
+                        parts.push(Token::Quoted(mem::take(&mut string)));
+                    }
+
+                    let part = self.read_expansion_token();
+
+                    parts.push(part.inspect_err(|_| self.index = start_index)?);
+                }
+
+                Some(c) => {
+                    string.push(c);
+                    self.advance();
+                }
+            }
+        }
+
+        if !string.is_empty() || parts.is_empty() {
+            parts.push(Token::Quoted(string));
+        }
+
+        Ok(parts)
     }
 
-    /// Reads a file descriptor token (e.g., `@1`, `@2`).
+    /// Reads a brace token (`{` or `}`) that opens or closes a block.
     ///
-    /// Returns an error if the format is invalid or the number is not an `i32`.
-    fn read_filedescriptor_token(&mut self) -> Result<Token> {
-        let start_index = self.index;
-
-        if self.current() != Some('@') {
+    /// A brace is only treated as a block delimiter when it stands alone as a word,
+    /// so words such as `{a,b}` are still read as strings.
+    fn read_brace_token(&mut self) -> Result<Token> {
+        let token = match self.current() {
+            Some('{') => Token::LeftBrace,
+            Some('}') => Token::RightBrace,
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        };
+
+        if self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, ';' | '&' | '|' | ')'))
+        {
             Err(Error::NOT_IMPLEMENTED)?
         }
 
         self.advance();
 
-        let string = self.read_while(|c| c.is_numeric());
-
-        string
-            .parse::<i32>()
-            .map(Token::FileDescriptor)
-            .map_err(|_| {
-                self.index = start_index;
-
-                Error::NOT_IMPLEMENTED
-            })
+        Ok(token)
     }
 
-    /// Returns the next token from the input stream.
+    /// Reads a here-document (`<<EOF` or `<<-EOF`) and its body.
     ///
-    /// Skips leading whitespace and dispatches based on the next character.
+    /// The body starts on the line after the operator and ends at a line consisting of the delimiter,
+    /// or at the end of input. It is removed from the input, so the rest of the operator's line is
+    /// read as usual. With `<<-`, leading tabs are removed from the body lines and the delimiter line.
     ///
-    /// Supported token types include:
-    /// - Punctuation: `;`, `&`, `|`, `=`, `<`, `>`
-    /// - Identifiers: `$name`
-    /// - File descriptors: `@1`
-    /// - Quoted strings: `"..."`, `'...'`
-    /// - Keywords: `true`, `false`, `null`
-    /// - Numbers: `123`
-    /// - Strings: non-reserved, non-numeric, unquoted words
+    /// If any part of the delimiter is quoted, the body is kept literally. Otherwise it is split
+    /// into literal strings and expansions, where a backslash only escapes `$`, `\` and a newline.
     ///
-    /// Returns `Token::EOF` when input is fully consumed.
-    pub fn next(&mut self) -> Result<Token> {
-        while let Some(c) = self.current() {
-            if c.is_whitespace() {
-                self.advance();
-                continue;
-            }
+    /// Returns an error if the delimiter is missing.
+    fn read_heredoc_token(&mut self) -> Result<Token> {
+        let start_index = self.index;
 
-            return match c {
-                ';' => {
-                    self.advance();
-                    Ok(Token::Semicolon)
-                }
+        self.index += 2;
 
-                '&' => {
-                    self.advance();
-                    Ok(Token::Ampersand)
-                }
+        let is_tab_stripped = self.current() == Some('-');
 
-                '|' => {
-                    self.advance();
-                    Ok(Token::Pipe)
-                }
+        if is_tab_stripped {
+            self.advance();
+        }
 
-                '=' => {
-                    self.advance();
-                    Ok(Token::Equal)
-                }
+        self.read_while(|c| c == ' ' || c == '\t');
 
-                '<' => {
-                    self.advance();
-                    Ok(Token::LessThan)
-                }
+        let (delimiter, is_quoted) = self.read_heredoc_delimiter();
 
-                '>' => {
-                    self.advance();
-                    Ok(Token::GreaterThan)
-                }
+        if delimiter.is_empty() {
+            self.index = start_index;
 
-                '$' => self.read_identifier_token(),
+            Err(Error::new(
+                ErrorKind::Other,
+                "syntax error: missing here-document delimiter",
+            ))?
+        }
 
-                '@' => self.read_filedescriptor_token(),
+        let body_start = self.source[self.index..]
+            .iter()
+            .position(|c| *c == '\n')
+            .map_or(self.source.len(), |n| self.index + n);
 
-                '\'' | '"' => self.read_quoted_string_token(),
+        let mut body = String::new();
 
-                '0'..='9' => self.read_number_token(),
+        let mut body_end = body_start;
 
-                _ => self
-                    .read_keyword_token()
-                    .or_else(|_| self.read_string_token()),
-            };
-        }
-        Ok(Token::EOF)
-    }
-}
+        while body_end + 1 < self.source.len() {
+            let line_start = body_end + 1;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+            body_end = self.source[line_start..]
+                .iter()
+                .position(|c| *c == '\n')
+                .map_or(self.source.len(), |n| line_start + n);
 
-    #[test]
-    fn test_reserved_keywords() {
-        let mut lexer = Lexer::new("null true false");
+            let line = self.source[line_start..body_end].iter().collect::<String>();
 
-        assert_eq!(lexer.next(), Ok(Token::Null));
+            let line = line.strip_suffix('\r').unwrap_or(&line);
 
-        assert_eq!(lexer.next(), Ok(Token::Boolean(true)));
+            let line = match is_tab_stripped {
+                true => line.trim_start_matches('\t'),
+                false => line,
+            };
 
-        assert_eq!(lexer.next(), Ok(Token::Boolean(false)));
+            if line == delimiter {
+                break;
+            }
 
-        assert_eq!(lexer.next().unwrap(), Token::EOF);
+            body.push_str(line);
+            body.push('\n');
+        }
+
+        // the newline ending the delimiter line is left to separate the following statement.
+        self.source.drain(body_start..body_end);
+
+        match is_quoted {
+            true => Ok(Token::HereDocument(vec![Token::String(body)])),
+            false => Lexer::new(body)
+                .read_heredoc_parts()
+                .map(Token::HereDocument)
+                .inspect_err(|_| self.index = start_index),
+        }
     }
 
-    #[test]
-    fn test_reserved_characters() {
-        let mut lexer = Lexer::new("; & | = < >");
+    /// Reads the delimiter word of a here-document, removing its quotes and backslashes.
+    ///
+    /// Returns the delimiter and whether any part of it was quoted.
+    fn read_heredoc_delimiter(&mut self) -> (String, bool) {
+        let mut delimiter = String::new();
 
-        assert_eq!(lexer.next().unwrap(), Token::Semicolon);
+        let mut is_quoted = false;
 
-        assert_eq!(lexer.next().unwrap(), Token::Ampersand);
+        while let Some(c) = self.current() {
+            match c {
+                c if c.is_whitespace() || matches!(c, ';' | '&' | '|' | '<' | '>') => break,
 
-        assert_eq!(lexer.next().unwrap(), Token::Pipe);
+                '\'' | '"' => {
+                    is_quoted = true;
 
-        assert_eq!(lexer.next().unwrap(), Token::Equal);
+                    self.advance();
 
-        assert_eq!(lexer.next().unwrap(), Token::LessThan);
+                    delimiter.push_str(&self.read_while(|x| x != c));
+                }
 
-        assert_eq!(lexer.next().unwrap(), Token::GreaterThan);
+                '\\' => {
+                    is_quoted = true;
 
-        assert_eq!(lexer.next().unwrap(), Token::EOF);
-    }
+                    self.advance();
 
-    #[test]
-    fn test_string_token() {
-        let mut lexer = Lexer::new("hello world");
+                    if let Some(c) = self.current() {
+                        delimiter.push(c);
+                    }
+                }
 
-        assert_eq!(lexer.next().unwrap(), Token::String("hello".to_string()));
+                c => delimiter.push(c),
+            }
 
-        assert_eq!(lexer.next().unwrap(), Token::String("world".to_string()));
+            self.advance();
+        }
 
-        assert_eq!(lexer.next().unwrap(), Token::EOF);
+        (delimiter, is_quoted)
     }
 
-    #[test]
-    fn test_quoted_string_token() {
-        let mut lexer = Lexer::new("\"hello world\" 'test string'");
+    /// Splits the body of a here-document into literal strings and expansions.
+    fn read_heredoc_parts(&mut self) -> Result<Vec<Token>> {
+        let mut parts = Vec::new();
 
-        assert_eq!(
-            lexer.next().unwrap(),
-            Token::String("hello world".to_string())
-        );
+        while let Some(c) = self.current() {
+            match (c, self.peek()) {
+                ('\\', Some(escaped @ ('$' | '\\'))) => {
+                    push_part(&mut parts, Token::String(escaped.to_string()));
+                    self.index += 2;
+                }
 
-        assert_eq!(
-            lexer.next().unwrap(),
-            Token::String("test string".to_string())
-        );
+                // an escaped newline joins the lines.
+                ('\\', Some('\n')) => self.index += 2,
 
-        assert_eq!(lexer.next().unwrap(), Token::EOF);
-    }
+                ('$', Some(next)) if is_expansion_start(next) => {
+                    let part = self.read_expansion_token()?;
+                    push_part(&mut parts, part);
+                }
 
-    #[test]
-    fn test_identifier_token() {
-        let mut lexer = Lexer::new("$identifier1");
-        assert_eq!(
-            lexer.next().unwrap(),
-            Token::Identifier("identifier1".to_string())
-        );
-        assert_eq!(lexer.next().unwrap(), Token::EOF);
+                (c, _) => {
+                    push_part(&mut parts, Token::String(c.to_string()));
+                    self.advance();
+                }
+            }
+        }
+
+        if parts.is_empty() {
+            parts.push(Token::String(String::new()));
+        }
+
+        Ok(parts)
     }
 
-    #[test]
-    fn test_number_token() {
-        let mut lexer = Lexer::new("123 4567 89");
+    /// Reads an expansion starting with `$`: an arithmetic substitution (`$((...))`),
+    /// a command substitution (`$(...)`), a parameter expansion (`${...}`), or an identifier (`$NAME`).
+    fn read_expansion_token(&mut self) -> Result<Token> {
+        match self.peek() {
+            Some('(') if self.source.get(self.index + 2) == Some(&'(') => self
+                .read_arithmetic_token()
+                .or_else(|_| self.read_substitution_token()),
+            Some('(') => self.read_substitution_token(),
+            Some('{') => self.read_parameter_token(),
+            _ => self.read_identifier_token(),
+        }
+    }
 
-        assert_eq!(lexer.next().unwrap(), Token::Number(123));
+    /// Reads an arithmetic substitution token (e.g., `$(($n + 1))`), holding the inner source.
+    ///
+    /// Returns an error if the substitution is not closed by `))`.
+    fn read_arithmetic_token(&mut self) -> Result<Token> {
+        let start_index = self.index;
 
-        assert_eq!(lexer.next().unwrap(), Token::Number(4567));
+        match self.read_substitution_token()? {
+            Token::Substitution(source) if source.len() >= 2 && source.ends_with(')') => {
+                Ok(Token::Arithmetic(source[1..source.len() - 1].to_string()))
+            }
 
-        assert_eq!(lexer.next().unwrap(), Token::Number(89));
+            _ => {
+                self.index = start_index;
 
-        assert_eq!(lexer.next().unwrap(), Token::EOF);
+                Err(Error::NOT_IMPLEMENTED)
+            }
+        }
     }
 
-    #[test]
-    fn test_filedescriptor_token() {
-        let mut lexer = Lexer::new("@0 @123");
+    /// Reads a braced parameter expansion token, e.g. `${HOME}`, `${#HOME}`, `${HOME:-/root}`,
+    /// `${PATH%%:*}`, `${NAME:1:3}` or `${PIPESTATUS[1]}`.
+    ///
+    /// The index of a list or a map (e.g., `[1]`) is kept in the name.
+    ///
+    /// The operand after the operator keeps its whitespace, and may contain quotes,
+    /// escapes and nested expansions.
+    ///
+    /// Returns an error if the expansion is malformed or the closing brace is missing.
+    fn read_parameter_token(&mut self) -> Result<Token> {
+        let start_index = self.index;
 
-        assert_eq!(lexer.next().unwrap(), Token::FileDescriptor(0));
+        if self.current() != Some('$') || self.peek() != Some('{') {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
 
-        assert_eq!(lexer.next().unwrap(), Token::FileDescriptor(123));
+        self.index += 2;
 
-        assert_eq!(lexer.next().unwrap(), Token::EOF);
+        self.read_parameter_body()
+            .inspect_err(|_| self.index = start_index)
     }
 
-    #[test]
-    fn test_mixed_tokens() {
-        let mut lexer = Lexer::new("$var1 = \"hello\" @3 < 100 & true;");
+    /// Reads the part of a parameter expansion after `${`, up to and including the closing brace.
+    fn read_parameter_body(&mut self) -> Result<Token> {
+        let mut name = String::new();
 
-        assert_eq!(lexer.next().unwrap(), Token::Identifier("var1".to_string()));
+        // `${#}` is the special parameter `#`, not the length form.
+        if self.current() == Some('#') && self.peek() != Some('}') {
+            name.push('#');
+            self.advance();
+        }
 
-        assert_eq!(lexer.next().unwrap(), Token::Equal);
+        let identifier = match self.current() {
+            Some(c) if SPECIAL_PARAMETERS.contains(&c) => {
+                self.advance();
 
-        assert_eq!(lexer.next().unwrap(), Token::String("hello".to_string()));
+                String::from(c)
+            }
+            _ => self.read_while(|c| c.is_ascii_alphanumeric() || c == '_'),
+        };
+
+        if identifier.is_empty() {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        name.push_str(&identifier);
+
+        if self.current() == Some('[') && !identifier.starts_with(SPECIAL_PARAMETERS) {
+            let accessor = self.read_while(|c| !matches!(c, ']' | '}'));
+
+            if self.current() != Some(']') {
+                Err(Error::NOT_IMPLEMENTED)?
+            }
+
+            self.advance();
+
+            name.push_str(&accessor);
+            name.push(']');
+        }
+
+        let mut operator = String::new();
+
+        if !name.starts_with('#') {
+            if self.current() == Some(':') {
+                operator.push(':');
+                self.advance();
+            }
+
+            match self.current() {
+                Some(c @ ('-' | '=' | '?' | '+')) => {
+                    operator.push(c);
+                    self.advance();
+                }
+
+                Some(c @ ('#' | '%')) if operator.is_empty() => {
+                    operator.push(c);
+                    self.advance();
+
+                    if self.current() == Some(c) {
+                        operator.push(c);
+                        self.advance();
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        let mut operand = Vec::new();
+
+        loop {
+            match self.current() {
+                None => Err(Error::NOT_IMPLEMENTED)?,
+
+                Some('}') => {
+                    self.advance();
+                    break;
+                }
+
+                Some(_) if operator.is_empty() => Err(Error::NOT_IMPLEMENTED)?,
+
+                Some('\\') => push_part(&mut operand, Token::Quoted(self.read_escape(false)?)),
+
+                Some('\'' | '"') => {
+                    for part in self.read_quoted_string_token()? {
+                        push_part(&mut operand, part);
+                    }
+                }
+
+                Some('$') if self.peek().is_some_and(is_expansion_start) => {
+                    let part = self.read_expansion_token()?;
+
+                    push_part(&mut operand, part);
+                }
+
+                Some('$') => {
+                    self.advance();
+
+                    push_part(&mut operand, Token::String(String::from('$')));
+                }
+
+                Some(_) => {
+                    let string = self.read_while(|c| !matches!(c, '\\' | '\'' | '"' | '$' | '}'));
+
+                    push_part(&mut operand, Token::String(string));
+                }
+            }
+        }
+
+        Ok(Token::Parameter(name, operator, operand))
+    }
+
+    /// Reads a shell variable identifier (variable key) token (e.g., `$HOME`).
+    ///
+    /// An identifier consists of ASCII letters, digits and underscores, or is the single character
+    /// of a special parameter (e.g., `$?`). If it is followed by an accessor (e.g., `$paths[0]`
+    /// or `$cfg.key`), a `Token::Index` is returned instead.
+    ///
+    /// Returns an error if the identifier is empty or malformed.
+    fn read_identifier_token(&mut self) -> Result<Token> {
+        let start_index = self.index;
+
+        if self.current() != Some('$') {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        self.advance();
+
+        if let Some(c) = self.current().filter(|c| SPECIAL_PARAMETERS.contains(c)) {
+            self.advance();
+
+            return Ok(Token::Identifier(String::from(c)));
+        }
+
+        let identifier = self.read_while(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        if identifier.is_empty() {
+            self.index = start_index;
+
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        if let Some(accessor) = self.read_accessor() {
+            return Ok(Token::Index(identifier, accessor));
+        }
+
+        Ok(Token::Identifier(identifier))
+    }
+
+    /// Reads the accessor following an identifier: `[index]` (e.g., `[0]`, `[-1]` or `[key]`)
+    /// or `.key`, and returns it as written.
+    ///
+    /// Nothing is consumed if there is no accessor.
+    fn read_accessor(&mut self) -> Option<String> {
+        let start_index = self.index;
+
+        let is_accessor = match self.current() {
+            Some('[') => {
+                self.advance();
+
+                let key = self.read_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+                let is_closed = self.current() == Some(']');
+
+                self.advance();
+
+                !key.is_empty() && is_closed
+            }
+
+            Some('.') => {
+                self.advance();
+
+                !self
+                    .read_while(|c| c.is_ascii_alphanumeric() || c == '_')
+                    .is_empty()
+            }
+
+            _ => false,
+        };
+
+        if !is_accessor {
+            self.index = start_index;
+
+            return None;
+        }
+
+        Some(self.source[start_index..self.index].iter().collect())
+    }
+
+    /// Reads a command substitution token (e.g., `$(git branch --show-current)`).
+    ///
+    /// The inner source is kept as-is and parsed later. Nested parentheses and
+    /// parentheses inside quotes are taken into account when looking for the closing `)`.
+    ///
+    /// Returns an error if the substitution is not terminated.
+    fn read_substitution_token(&mut self) -> Result<Token> {
+        if self.current() != Some('$') || self.peek() != Some('(') {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        self.read_parenthesized_source().map(Token::Substitution)
+    }
+
+    /// Reads a process substitution token, `<(sort a.txt)` or `>(gzip > out.gz)`,
+    /// whose inner source is read as that of a command substitution.
+    ///
+    /// Returns an error if the substitution is not terminated.
+    fn read_process_substitution_token(&mut self) -> Result<Token> {
+        if self.peek() != Some('(') {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        match self.current() {
+            Some('<') => self
+                .read_parenthesized_source()
+                .map(Token::InputSubstitution),
+            Some('>') => self
+                .read_parenthesized_source()
+                .map(Token::OutputSubstitution),
+            _ => Err(Error::NOT_IMPLEMENTED),
+        }
+    }
+
+    /// Reads the source between a two-character opener (e.g., `$(`) and the matching `)`.
+    fn read_parenthesized_source(&mut self) -> Result<String> {
+        let start_index = self.index;
+
+        self.index += 2;
+
+        let mut depth = 1usize;
+
+        let mut quote = None;
+
+        let mut string = String::new();
+
+        while let Some(c) = self.current() {
+            self.advance();
+
+            match (quote, c) {
+                // the escaped character is kept for the inner source to be lexed again.
+                (None | Some('"'), '\\') => {
+                    if let Some(escaped) = self.current() {
+                        string.push(c);
+                        string.push(escaped);
+                        self.advance();
+                        continue;
+                    }
+                }
+
+                (Some(q), c) if c == q => quote = None,
+
+                (Some(_), _) => {}
+
+                (None, '\'' | '"') => quote = Some(c),
+
+                (None, '(') => depth += 1,
+
+                (None, ')') => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return Ok(string);
+                    }
+                }
+
+                _ => {}
+            }
+
+            string.push(c);
+        }
+
+        self.index = start_index;
+
+        Err(Error::NOT_IMPLEMENTED)
+    }
+
+    /// Reads a file descriptor token (e.g., `@1`, `@2`).
+    ///
+    /// Returns an error if the format is invalid or the number is not an `i32`.
+    fn read_filedescriptor_token(&mut self) -> Result<Token> {
+        let start_index = self.index;
+
+        if self.current() != Some('@') {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        self.advance();
+
+        let string = self.read_while(|c| c.is_numeric());
+
+        string
+            .parse::<i32>()
+            .map(Token::FileDescriptor)
+            .map_err(|_| {
+                self.index = start_index;
+
+                Error::NOT_IMPLEMENTED
+            })
+    }
+
+    /// Returns the next token from the input stream.
+    ///
+    /// Skips leading whitespace and dispatches based on the next character.
+    ///
+    /// Supported token types include:
+    /// - Punctuation: `;`, `&`, `|`, `&&`, `||`, `=`, `<`, `>`, `>>`, `>|`, `<>`, `<<<`
+    /// - Here-documents: `<<EOF`, `<<-EOF` with their body lines
+    /// - Block delimiters: `{`, `}` (only as standalone words)
+    /// - Identifiers: `$name`
+    /// - Command substitutions: `$(...)`
+    /// - File descriptors: `@1`
+    /// - Quoted strings: `"..."` (interpolating `$name` and `$(...)`), `'...'`
+    /// - Keywords: `true`, `false`, `null`
+    /// - Numbers: `123`
+    /// - Strings: non-reserved, non-numeric, unquoted words
+    /// - Words: adjacent parts of the above forming a single argument, e.g. `"$HOME"/bin`
+    ///
+    /// Returns `Token::EOF` when input is fully consumed.
+    pub fn next(&mut self) -> Result<Token> {
+        while let Some(c) = self.current() {
+            if c.is_whitespace() {
+                self.advance();
+                continue;
+            }
+
+            return match c {
+                ';' => {
+                    self.advance();
+                    Ok(Token::Semicolon)
+                }
+
+                '&' => {
+                    self.advance();
+
+                    if self.current() == Some('&') {
+                        self.advance();
+                        Ok(Token::DoubleAmpersand)
+                    } else {
+                        Ok(Token::Ampersand)
+                    }
+                }
+
+                '|' => {
+                    self.advance();
+
+                    if self.current() == Some('|') {
+                        self.advance();
+                        Ok(Token::DoublePipe)
+                    } else {
+                        Ok(Token::Pipe)
+                    }
+                }
+
+                // `==` is a word, e.g. the comparison operator of `test`.
+                '=' if self.peek() == Some('=') => self.read_word_token(),
+
+                '=' => {
+                    self.advance();
+                    Ok(Token::Equal)
+                }
+
+                '<' | '>' if self.peek() == Some('(') => self.read_process_substitution_token(),
+
+                '<' if self.peek() == Some('<')
+                    && self.source.get(self.index + 2) == Some(&'<') =>
+                {
+                    self.index += 3;
+                    Ok(Token::TripleLessThan)
+                }
+
+                '<' if self.peek() == Some('<') => self.read_heredoc_token(),
+
+                '<' if self.peek() == Some('>') => {
+                    self.index += 2;
+                    Ok(Token::LessThanGreaterThan)
+                }
+
+                '<' => {
+                    self.advance();
+                    Ok(Token::LessThan)
+                }
+
+                '>' if self.peek() == Some('>') => {
+                    self.index += 2;
+                    Ok(Token::DoubleGreaterThan)
+                }
+
+                '>' if self.peek() == Some('|') => {
+                    self.index += 2;
+                    Ok(Token::GreaterThanPipe)
+                }
+
+                '>' => {
+                    self.advance();
+                    Ok(Token::GreaterThan)
+                }
+
+                '@' => self.read_filedescriptor_token(),
+
+                '(' => {
+                    self.advance();
+                    Ok(Token::LeftParen)
+                }
+
+                ')' => {
+                    self.advance();
+                    Ok(Token::RightParen)
+                }
+
+                _ => self.read_brace_token().or_else(|_| self.read_word_token()),
+            };
+        }
+        Ok(Token::EOF)
+    }
+}
+
+/// Returns `true` if `c` may follow a `$` to start an identifier, a parameter expansion
+/// or a command substitution.
+fn is_expansion_start(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '(' || c == '{' || SPECIAL_PARAMETERS.contains(&c)
+}
+
+/// Appends a part to the parts of a word, merging adjacent literal strings that are
+/// both quoted or both unquoted.
+fn push_part(parts: &mut Vec<Token>, part: Token) {
+    match (parts.last_mut(), part) {
+        (Some(Token::String(last)), Token::String(string)) => last.push_str(&string),
+        (Some(Token::Quoted(last)), Token::Quoted(string)) => last.push_str(&string),
+        (_, part) => parts.push(part),
+    }
+}
+
+/// Classifies an unquoted word as a keyword (`null`, `true`, `false`), a number, or a string.
+///
+/// A word is only read as a number if it is written canonically,
+/// so words such as `0755` or `+1` are kept as strings.
+fn keyword_or_number(string: String) -> Token {
+    match string {
+        val if val == RESERVED_KEYWORDS[0] => Token::Null,
+
+        val if val == RESERVED_KEYWORDS[1] => Token::Boolean(true),
+
+        val if val == RESERVED_KEYWORDS[2] => Token::Boolean(false),
+
+        val => match val.parse::<isize>() {
+            Ok(number) if number.to_string() == val => Token::Number(number),
+            _ => Token::String(val),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserved_keywords() {
+        let mut lexer = Lexer::new("null true false");
+
+        assert_eq!(lexer.next(), Ok(Token::Null));
+
+        assert_eq!(lexer.next(), Ok(Token::Boolean(true)));
+
+        assert_eq!(lexer.next(), Ok(Token::Boolean(false)));
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_reserved_characters() {
+        let mut lexer = Lexer::new("; & | = < >");
+
+        assert_eq!(lexer.next().unwrap(), Token::Semicolon);
+
+        assert_eq!(lexer.next().unwrap(), Token::Ampersand);
+
+        assert_eq!(lexer.next().unwrap(), Token::Pipe);
+
+        assert_eq!(lexer.next().unwrap(), Token::Equal);
+
+        assert_eq!(lexer.next().unwrap(), Token::LessThan);
+
+        assert_eq!(lexer.next().unwrap(), Token::GreaterThan);
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let mut lexer = Lexer::new("a && b || c & d | e");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("a".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::DoubleAmpersand);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("b".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::DoublePipe);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("c".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Ampersand);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("d".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Pipe);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("e".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_brace_token() {
+        let mut lexer = Lexer::new("if true {;echo {a,b};}");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("if".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Boolean(true));
+
+        assert_eq!(lexer.next().unwrap(), Token::LeftBrace);
+
+        assert_eq!(lexer.next().unwrap(), Token::Semicolon);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("echo".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::String("{a,b}".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Semicolon);
+
+        assert_eq!(lexer.next().unwrap(), Token::RightBrace);
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_paren_token() {
+        let mut lexer = Lexer::new("(cd /tmp; ls !(*.o|*.a))");
+
+        assert_eq!(lexer.next().unwrap(), Token::LeftParen);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("cd".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::String("/tmp".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Semicolon);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("ls".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::String("!(*.o|*.a)".to_string())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::RightParen);
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_string_token() {
+        let mut lexer = Lexer::new("hello world");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("hello".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::String("world".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_quoted_string_token() {
+        let mut lexer = Lexer::new("\"hello world\" 'test string'");
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("hello world".to_string())])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("test string".to_string())])
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_identifier_token() {
+        let mut lexer = Lexer::new("$identifier1");
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Identifier("identifier1".to_string())
+        );
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_special_parameter_token() {
+        let mut lexer = Lexer::new("$? \"$?\"x ${?} $$ $# ${#} ${#@}");
+
+        assert_eq!(lexer.next().unwrap(), Token::Identifier("?".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Identifier("?".to_string()),
+                Token::String("x".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter("?".to_string(), String::new(), Vec::new())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::Identifier("$".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Identifier("#".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter("#".to_string(), String::new(), Vec::new())
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter("#@".to_string(), String::new(), Vec::new())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_substitution_token() {
+        let mut lexer = Lexer::new("echo $(git branch --show-current) $(echo $(echo \")\"))");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("echo".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Substitution("git branch --show-current".to_string())
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Substitution("echo $(echo \")\")".to_string())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_process_substitution_token() {
+        let mut lexer = Lexer::new("diff <(sort a) >(gzip > (b)) < <(ls)");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("diff".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::InputSubstitution("sort a".to_string())
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::OutputSubstitution("gzip > (b)".to_string())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::LessThan);
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::InputSubstitution("ls".to_string())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+
+        assert!(Lexer::new("<(ls").next().is_err());
+    }
+
+    #[test]
+    fn test_invalid_substitution() {
+        let mut lexer = Lexer::new("$(echo");
+
+        assert!(lexer.next().is_err());
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let mut lexer =
+            Lexer::new(r#"a\ b \$HOME\; "say \"hi\"\t\$x \d" 'raw\n' \false $(echo \))"#);
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::String("a".to_string()),
+                Token::Quoted(" ".to_string()),
+                Token::String("b".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Quoted("$".to_string()),
+                Token::String("HOME".to_string()),
+                Token::Quoted(";".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("say \"hi\"\t$x \\d".to_string())])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("raw\\n".to_string())])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Quoted("f".to_string()),
+                Token::String("alse".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Substitution("echo \\)".to_string())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_double_quoted_escapes() {
+        let mut lexer = Lexer::new(r#""a\tb" "a\qb""#);
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("a\tb".to_string())])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("a\\qb".to_string())])
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_trailing_backslash() {
+        let mut lexer = Lexer::new("echo \\");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("echo".to_string()));
+
+        assert!(lexer.next().is_err());
+    }
+
+    #[test]
+    fn test_parameter_token() {
+        let mut lexer =
+            Lexer::new("${HOME} ${#HOME} ${X:-a $Y} ${P##*/} \"${N:1:2}\" ${S[1]:-0} ${X");
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter("HOME".to_string(), "".to_string(), vec![])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter("#HOME".to_string(), "".to_string(), vec![])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter(
+                "X".to_string(),
+                ":-".to_string(),
+                vec![
+                    Token::String("a ".to_string()),
+                    Token::Identifier("Y".to_string())
+                ]
+            )
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter(
+                "P".to_string(),
+                "##".to_string(),
+                vec![Token::String("*/".to_string())]
+            )
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter(
+                "N".to_string(),
+                ":".to_string(),
+                vec![Token::String("1:2".to_string())]
+            )
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Parameter(
+                "S[1]".to_string(),
+                ":-".to_string(),
+                vec![Token::String("0".to_string())]
+            )
+        );
+
+        assert!(lexer.next().is_err());
+    }
+
+    #[test]
+    fn test_index_token() {
+        let mut lexer = Lexer::new("$paths[0] $cfg.key \"$f.bak\" $x[ $y. $z[a b]");
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Index("paths".to_string(), "[0]".to_string())
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Index("cfg".to_string(), ".key".to_string())
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Index("f".to_string(), ".bak".to_string())
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Identifier("x".to_string()),
+                Token::String("[".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Identifier("y".to_string()),
+                Token::String(".".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Identifier("z".to_string()),
+                Token::String("[a".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_double_equal() {
+        let mut lexer = Lexer::new("a == b = c");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("a".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::String("==".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::String("b".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Equal);
+    }
+
+    #[test]
+    fn test_redirect_tokens() {
+        let mut lexer = Lexer::new("a >> b >| c <> d > e");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("a".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::DoubleGreaterThan);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("b".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::GreaterThanPipe);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("c".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::LessThanGreaterThan);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("d".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::GreaterThan);
+    }
+
+    #[test]
+    fn test_heredoc_token() {
+        let mut lexer = Lexer::new("cat <<EOF | sort\n$x \\$y\nEOF\n;cat <<-'E' <<< z\n\t$x\n\tE");
+
+        assert_eq!(lexer.next().unwrap(), Token::String("cat".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::HereDocument(vec![
+                Token::Identifier("x".to_string()),
+                Token::String(" $y\n".to_string())
+            ])
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::Pipe);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("sort".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Semicolon);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("cat".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::HereDocument(vec![Token::String("$x\n".to_string())])
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::TripleLessThan);
+
+        assert_eq!(lexer.next().unwrap(), Token::String("z".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+
+        assert!(Lexer::new("cat <<").next().is_ok());
+
+        let mut lexer = Lexer::new("<< ;");
+
+        assert!(lexer.next().is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_token() {
+        let mut lexer = Lexer::new("$(($n + (2 * 3))) \"$((1+1))\" $( (pwd) )");
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Arithmetic("$n + (2 * 3)".to_string())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::Arithmetic("1+1".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Substitution(" (pwd) ".to_string())
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_word_token() {
+        let mut lexer = Lexer::new("foo$BAR \"$HOME/bin\" '$HOME'/x --opt=a@b \"a $(pwd)\" $a=1");
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::String("foo".to_string()),
+                Token::Identifier("BAR".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Identifier("HOME".to_string()),
+                Token::Quoted("/bin".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Quoted("$HOME".to_string()),
+                Token::String("/x".to_string())
+            ])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::String("--opt=a@b".to_string())
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![
+                Token::Quoted("a ".to_string()),
+                Token::Substitution("pwd".to_string())
+            ])
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::Identifier("a".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Equal);
+
+        assert_eq!(lexer.next().unwrap(), Token::Number(1));
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_quoted_keyword_and_number() {
+        let mut lexer = Lexer::new("\"true\" '123' 0755 \"\"");
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("true".to_string())])
+        );
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("123".to_string())])
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::String("0755".to_string()));
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("".to_string())])
+        );
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_number_token() {
+        let mut lexer = Lexer::new("123 4567 89");
+
+        assert_eq!(lexer.next().unwrap(), Token::Number(123));
+
+        assert_eq!(lexer.next().unwrap(), Token::Number(4567));
+
+        assert_eq!(lexer.next().unwrap(), Token::Number(89));
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_filedescriptor_token() {
+        let mut lexer = Lexer::new("@0 @123");
+
+        assert_eq!(lexer.next().unwrap(), Token::FileDescriptor(0));
+
+        assert_eq!(lexer.next().unwrap(), Token::FileDescriptor(123));
+
+        assert_eq!(lexer.next().unwrap(), Token::EOF);
+    }
+
+    #[test]
+    fn test_mixed_tokens() {
+        let mut lexer = Lexer::new("$var1 = \"hello\" @3 < 100 & true;");
+
+        assert_eq!(lexer.next().unwrap(), Token::Identifier("var1".to_string()));
+
+        assert_eq!(lexer.next().unwrap(), Token::Equal);
+
+        assert_eq!(
+            lexer.next().unwrap(),
+            Token::Word(vec![Token::Quoted("hello".to_string())])
+        );
 
         assert_eq!(lexer.next().unwrap(), Token::FileDescriptor(3));
 
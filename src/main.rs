@@ -1,17 +1,20 @@
 use std::{
-    env,
+    env, fs,
     io::{self, Write},
+    path::{Path, PathBuf},
     process,
 };
 
 use fsh::{
     execute::execute,
     parser::Parser,
+    preprocessor,
     profile::{self, DEFAULT_PROFILE_CONTENT},
     prompt,
     sh_vars::ShVars,
     state::State,
     terminal::Terminal,
+    utils,
 };
 
 use clap;
@@ -48,17 +51,26 @@ fn welcome_art() {
 struct AppArgs {
     #[clap(long="profile", short='p', default_value = profile::DEFAULT_PROFILE_PATH)]
     profile: String,
+
+    /// A script to run instead of reading commands from the terminal.
+    script: Option<PathBuf>,
+
+    /// The positional arguments of the script (`$1`, `$2`, ...).
+    #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
+    arguments: Vec<String>,
 }
 
-fn parse_app_args() -> String {
+fn parse_app_args() -> (PathBuf, Option<PathBuf>, Vec<String>) {
     let args = <AppArgs as clap::Parser>::parse();
 
-    args.profile
+    (
+        utils::path::expand_tilde_to_home_dir(args.profile),
+        args.script,
+        args.arguments,
+    )
 }
 
-fn initialize() -> (State, ShVars) {
-    let path = parse_app_args();
-
+fn initialize(path: PathBuf) -> (State, ShVars) {
     let profile_content = profile::exists(&path)
         .then(|| profile::read_profile(&path))
         .map_or_else(
@@ -88,6 +100,20 @@ fn initialize() -> (State, ShVars) {
 
     let mut sh_vars = ShVars::from(env::vars());
 
+    // a shell started from another one is one level deeper.
+    let level = sh_vars
+        .get("SHLVL")
+        .and_then(|level| level.parse::<i32>().ok())
+        .unwrap_or(0);
+
+    if sh_vars
+        .insert("SHLVL", (level + 1).to_string())
+        .and_then(|_| sh_vars.export("SHLVL"))
+        .is_err()
+    {
+        process::exit(1);
+    }
+
     if let Err(err) = Parser::new(profile_content)
         .parse()
         .map(|ast| execute(ast, &mut state, &mut sh_vars))
@@ -106,18 +132,77 @@ fn initialize() -> (State, ShVars) {
     (state, sh_vars)
 }
 
+/// Runs a script, then exits with the exit status of its last command.
+///
+/// A syntax error exits with the status 2, and an error that leaves the status at 0 with 1.
+fn run_script(path: &Path, state: &mut State, sh_vars: &mut ShVars) -> ! {
+    let source = fs::read_to_string(path).unwrap_or_else(|err| {
+        stderr(format!("fsh: {}: {}\n", path.display(), err).as_str());
+
+        process::exit(127);
+    });
+
+    match Parser::new(source).parse() {
+        Ok(ast) => {
+            if let Err(err) = execute(ast, state, sh_vars) {
+                stderr(format!("fsh: {}\n", err).as_str());
+
+                if state.exit_status() == 0 {
+                    *state.exit_status_mut() = 1;
+                }
+            }
+        }
+        Err(err) => {
+            stderr(format!("fsh: {}\n", err).as_str());
+
+            *state.exit_status_mut() = 2;
+        }
+    }
+
+    process::exit(state.exit_status())
+}
+
 fn main() {
-    welcome_art();
+    let (profile, script, arguments) = parse_app_args();
+
+    if script.is_none() {
+        welcome_art();
+    }
+
+    let (mut state, mut sh_vars) = initialize(profile);
 
-    let (mut state, mut sh_vars) = initialize();
+    if let Some(script) = script {
+        *state.script_name_mut() = script.to_string_lossy().to_string();
+
+        *state.arguments_mut() = arguments;
+
+        run_script(&script, &mut state, &mut sh_vars);
+    }
 
     let mut terminal = Terminal::new();
 
-    terminal.set_prompt(prompt::decode(
-        sh_vars.get("FSH_PROMPT").map_or("> ", |string| string),
-    ));
+    let prompt = prompt::decode(sh_vars.get("FSH_PROMPT").map_or("> ", |string| string));
+
+    terminal.set_prompt(prompt.clone());
+
+    while let Ok(mut string) = terminal.read_line() {
+        // a here-document goes on over the following lines, up to its delimiter line.
+        if preprocessor::is_heredoc_unterminated(&string) {
+            terminal.set_prompt("> ");
+
+            while preprocessor::is_heredoc_unterminated(&string) {
+                match terminal.read_line() {
+                    Ok(line) => {
+                        string.push('\n');
+                        string.push_str(&line);
+                    }
+                    Err(_) => break,
+                }
+            }
+
+            terminal.set_prompt(prompt.clone());
+        }
 
-    while let Ok(string) = terminal.read_line() {
         let mut parser = Parser::new(string);
 
         match parser.parse() {
@@ -129,6 +214,10 @@ fn main() {
                     //     stderr(format!("fsh: {}\n", err).as_str());
                     // }
                     stderr(format!("fsh: {}\n", err).as_str());
+
+                    if state.exit_status() == 0 {
+                        *state.exit_status_mut() = 1;
+                    }
                 }
             }
             Err(err) => {
@@ -138,7 +227,11 @@ fn main() {
                 //     stderr(format!("fsh: {}\n", err).as_str());
                 // }
                 stderr(format!("fsh: {}\n", err).as_str());
+
+                *state.exit_status_mut() = 2;
             }
         }
     }
+
+    process::exit(state.exit_status());
 }
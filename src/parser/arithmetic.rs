@@ -0,0 +1,358 @@
+use crate::{
+    ast::{arithmetic::*, expression::Expression},
+    error::Error,
+    result::Result,
+    token::Token,
+};
+
+use super::lite;
+
+/// Binary operators grouped by precedence, from the loosest to the tightest binding.
+///
+/// `**` binds tighter than the unary operators and is handled separately.
+const BINARY_OPERATORS: [&[(&str, ArithmeticOperator)]; 4] = [
+    &[
+        ("==", ArithmeticOperator::Equal),
+        ("!=", ArithmeticOperator::NotEqual),
+    ],
+    &[
+        ("<", ArithmeticOperator::LessThan),
+        ("<=", ArithmeticOperator::LessThanOrEqual),
+        (">", ArithmeticOperator::GreaterThan),
+        (">=", ArithmeticOperator::GreaterThanOrEqual),
+    ],
+    &[
+        ("+", ArithmeticOperator::Add),
+        ("-", ArithmeticOperator::Subtract),
+    ],
+    &[
+        ("*", ArithmeticOperator::Multiply),
+        ("/", ArithmeticOperator::Divide),
+        ("%", ArithmeticOperator::Remainder),
+    ],
+];
+
+const UNARY_OPERATORS: &[(&str, ArithmeticOperator)] = &[
+    ("-", ArithmeticOperator::Subtract),
+    ("+", ArithmeticOperator::Add),
+    ("!", ArithmeticOperator::Not),
+];
+
+/// Operators made of two characters, which the lexer may split into separate tokens.
+const COMPOUND_OPERATORS: &[&str] = &["**", "==", "!=", "<=", ">="];
+
+/// The smallest unit of an arithmetic expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Atom {
+    Number(isize),
+    Variable(String),
+    Expansion(Expression),
+    Operator(String),
+}
+
+/// Appends an operator character, merging it with the previous one into a compound operator.
+fn push_operator(atoms: &mut Vec<Atom>, c: char) {
+    if let Some(Atom::Operator(previous)) = atoms.last_mut() {
+        let compound = format!("{previous}{c}");
+
+        if COMPOUND_OPERATORS.contains(&compound.as_str()) {
+            *previous = compound;
+            return;
+        }
+    }
+
+    atoms.push(Atom::Operator(c.to_string()));
+}
+
+/// Splits a literal string (e.g., `2*(3+x)`) into numbers, variables, and operators.
+fn scan(string: &str, atoms: &mut Vec<Atom>) -> Result<()> {
+    let chars = string.chars().collect::<Vec<_>>();
+
+    let mut index = 0;
+
+    while let Some(&c) = chars.get(index) {
+        if c.is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        if "+-*/%<>=!()".contains(c) {
+            push_operator(atoms, c);
+            index += 1;
+            continue;
+        }
+
+        if !c.is_ascii_alphanumeric() && c != '_' {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        let start = index;
+
+        while chars
+            .get(index)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            index += 1;
+        }
+
+        let word = chars[start..index].iter().collect::<String>();
+
+        if c.is_ascii_digit() {
+            let number = word.parse::<isize>().map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+            atoms.push(Atom::Number(number));
+        } else {
+            atoms.push(Atom::Variable(word));
+        }
+    }
+
+    Ok(())
+}
+
+/// Converts lexer tokens into the atoms of an arithmetic expression.
+fn atomize(tokens: &[Token], atoms: &mut Vec<Atom>) -> Result<()> {
+    for token in tokens {
+        match token {
+            Token::Number(number) => atoms.push(Atom::Number(*number)),
+
+            Token::Identifier(identifier) => atoms.push(Atom::Variable(identifier.to_string())),
+
+            Token::String(string) => scan(string, atoms)?,
+
+            Token::LessThan => push_operator(atoms, '<'),
+
+            Token::GreaterThan => push_operator(atoms, '>'),
+
+            Token::Equal => push_operator(atoms, '='),
+
+            Token::LeftParen => push_operator(atoms, '('),
+
+            Token::RightParen => push_operator(atoms, ')'),
+
+            Token::Index(..) => atoms.push(Atom::Expansion(lite::parse_index(token)?)),
+
+            Token::Parameter(..) => atoms.push(Atom::Expansion(lite::parse_parameter(token)?)),
+
+            Token::Substitution(_) => atoms.push(Atom::Expansion(lite::parse_substitution(token)?)),
+
+            Token::Arithmetic(_) => {
+                atoms.push(Atom::Expansion(lite::parse_arithmetic_substitution(token)?))
+            }
+
+            Token::Word(parts) => atomize(parts, atoms)?,
+
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// A recursive descent parser over the atoms of an arithmetic expression.
+struct ArithmeticParser {
+    atoms: Vec<Atom>,
+    index: usize,
+}
+
+impl ArithmeticParser {
+    /// Consumes the current atom if it is one of the given operators.
+    fn next_operator(
+        &mut self,
+        operators: &[(&str, ArithmeticOperator)],
+    ) -> Option<ArithmeticOperator> {
+        let Some(Atom::Operator(current)) = self.atoms.get(self.index) else {
+            return None;
+        };
+
+        let (_, operator) = operators.iter().find(|(symbol, _)| symbol == current)?;
+
+        self.index += 1;
+
+        Some(*operator)
+    }
+
+    /// Parses the binary operators of the given precedence level and tighter.
+    fn parse_binary(&mut self, level: usize) -> Result<Arithmetic> {
+        let Some(operators) = BINARY_OPERATORS.get(level) else {
+            return self.parse_unary();
+        };
+
+        let mut left = self.parse_binary(level + 1)?;
+
+        while let Some(operator) = self.next_operator(operators) {
+            let right = self.parse_binary(level + 1)?;
+
+            left = Arithmetic::Binary(operator, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Arithmetic> {
+        match self.next_operator(UNARY_OPERATORS) {
+            Some(operator) => Ok(Arithmetic::Unary(operator, Box::new(self.parse_unary()?))),
+            None => self.parse_power(),
+        }
+    }
+
+    /// Parses `**`, which is right-associative.
+    fn parse_power(&mut self) -> Result<Arithmetic> {
+        let base = self.parse_primary()?;
+
+        match self.next_operator(&[("**", ArithmeticOperator::Power)]) {
+            Some(operator) => Ok(Arithmetic::Binary(
+                operator,
+                Box::new(base),
+                Box::new(self.parse_unary()?),
+            )),
+            None => Ok(base),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Arithmetic> {
+        let atom = self.atoms.get(self.index).cloned();
+
+        self.index += 1;
+
+        match atom.ok_or(Error::NOT_IMPLEMENTED)? {
+            Atom::Number(number) => Ok(Arithmetic::Number(number)),
+
+            Atom::Variable(variable) => Ok(Arithmetic::Variable(variable)),
+
+            Atom::Expansion(expression) => Ok(Arithmetic::Expansion(Box::new(expression))),
+
+            Atom::Operator(operator) if operator == "(" => {
+                let arithmetic = self.parse_binary(0)?;
+
+                match self.atoms.get(self.index) {
+                    Some(Atom::Operator(operator)) if operator == ")" => {
+                        self.index += 1;
+
+                        Ok(arithmetic)
+                    }
+
+                    _ => Err(Error::NOT_IMPLEMENTED),
+                }
+            }
+
+            _ => Err(Error::NOT_IMPLEMENTED),
+        }
+    }
+}
+
+/// Parses an arithmetic expression from a sequence of tokens, e.g. `$n + 1` or `2 ** (3 - 1)`.
+///
+/// Variables may be written with or without a leading `$`.
+///
+/// # Returns
+/// - `Ok(Arithmetic)` if the whole sequence is a valid expression.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_arithmetic(tokens: &[Token]) -> Result<Arithmetic> {
+    let mut atoms = Vec::with_capacity(tokens.len());
+
+    atomize(tokens, &mut atoms)?;
+
+    let mut parser = ArithmeticParser { atoms, index: 0 };
+
+    let arithmetic = parser.parse_binary(0)?;
+
+    if parser.index != parser.atoms.len() {
+        Err(Error::NOT_IMPLEMENTED)?
+    }
+
+    Ok(arithmetic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary(operator: ArithmeticOperator, left: Arithmetic, right: Arithmetic) -> Arithmetic {
+        Arithmetic::Binary(operator, Box::new(left), Box::new(right))
+    }
+
+    #[test]
+    fn test_parse_arithmetic() {
+        let tokens = [
+            Token::Identifier("n".to_string()),
+            Token::String("+".to_string()),
+            Token::Number(2),
+            Token::String("*".to_string()),
+            Token::Number(3),
+        ];
+
+        assert_eq!(
+            parse_arithmetic(&tokens).unwrap(),
+            binary(
+                ArithmeticOperator::Add,
+                Arithmetic::Variable("n".to_string()),
+                binary(
+                    ArithmeticOperator::Multiply,
+                    Arithmetic::Number(2),
+                    Arithmetic::Number(3)
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_arithmetic_precedence() {
+        let tokens = [Token::String("(1+2)*-2**3**2".to_string())];
+
+        assert_eq!(
+            parse_arithmetic(&tokens).unwrap(),
+            binary(
+                ArithmeticOperator::Multiply,
+                binary(
+                    ArithmeticOperator::Add,
+                    Arithmetic::Number(1),
+                    Arithmetic::Number(2)
+                ),
+                Arithmetic::Unary(
+                    ArithmeticOperator::Subtract,
+                    Box::new(binary(
+                        ArithmeticOperator::Power,
+                        Arithmetic::Number(2),
+                        binary(
+                            ArithmeticOperator::Power,
+                            Arithmetic::Number(3),
+                            Arithmetic::Number(2)
+                        )
+                    ))
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_arithmetic_comparison() {
+        let tokens = [
+            Token::Identifier("a".to_string()),
+            Token::LessThan,
+            Token::Equal,
+            Token::Number(3),
+        ];
+
+        assert_eq!(
+            parse_arithmetic(&tokens).unwrap(),
+            binary(
+                ArithmeticOperator::LessThanOrEqual,
+                Arithmetic::Variable("a".to_string()),
+                Arithmetic::Number(3)
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_arithmetic_failure() {
+        assert!(parse_arithmetic(&[Token::String("(1+2".to_string())]).is_err());
+
+        assert!(parse_arithmetic(&[Token::Number(1), Token::Number(2)]).is_err());
+
+        assert!(parse_arithmetic(&[Token::String("1=2".to_string())]).is_err());
+
+        assert!(parse_arithmetic(&[Token::Ampersand]).is_err());
+
+        assert!(parse_arithmetic(&[]).is_err());
+    }
+}
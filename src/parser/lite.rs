@@ -1,10 +1,13 @@
 use crate::{
     ast::{expression::*, statement::*},
-    error::Error,
+    error::{Error, ErrorKind},
+    lexer::Lexer,
     result::Result,
     token::Token,
 };
 
+use super::{arithmetic, Parser};
+
 /// Parses a `null` literal token into an `Expression::Null`.
 ///
 /// # Arguments
@@ -35,6 +38,21 @@ pub fn parse_string(token: &Token) -> Result<Expression> {
     }
 }
 
+/// Parses a quoted part of a word into an `Expression::Quoted`.
+///
+/// # Arguments
+/// - `token`: The token to parse.
+///
+/// # Returns
+/// - `Ok(Expression::Quoted)` with the cloned text if the token is `Token::Quoted`.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_quoted(token: &Token) -> Result<Expression> {
+    match token {
+        Token::Quoted(s) => Ok(Expression::Quoted(s.clone())),
+        _ => Err(Error::NOT_IMPLEMENTED),
+    }
+}
+
 /// Parses an identifier token into an `Expression::Identifier`.
 ///
 /// # Arguments
@@ -50,6 +68,23 @@ pub fn parse_identifier(token: &Token) -> Result<Expression> {
     }
 }
 
+/// Parses an index token (e.g., `$paths[0]` or `$cfg.key`) into an `Expression::Index`.
+///
+/// # Arguments
+/// - `token`: The token to parse.
+///
+/// # Returns
+/// - `Ok(Expression::Index)` if the token is `Token::Index`.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_index(token: &Token) -> Result<Expression> {
+    match token {
+        Token::Index(identifier, accessor) => {
+            Ok(Expression::Index(identifier.clone(), accessor.clone()))
+        }
+        _ => Err(Error::NOT_IMPLEMENTED),
+    }
+}
+
 /// Parses a boolean literal token into an `Expression::Boolean`.
 ///
 /// # Arguments
@@ -95,10 +130,199 @@ pub fn parse_file_descriptor(token: &Token) -> Result<Expression> {
     }
 }
 
+/// Parses a command substitution token into an `Expression::Substitution`.
+///
+/// The inner source is parsed as a complete FSH program.
+///
+/// # Arguments
+/// - `token`: The token to parse.
+///
+/// # Returns
+/// - `Ok(Expression::Substitution)` if the token is `Token::Substitution` and its source parses.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_substitution(token: &Token) -> Result<Expression> {
+    match token {
+        Token::Substitution(source) => Parser::new(source.as_str())
+            .parse()
+            .map(|statement| Expression::Substitution(Box::new(statement))),
+        _ => Err(Error::NOT_IMPLEMENTED),
+    }
+}
+
+/// Parses a process substitution token into an `Expression::InputSubstitution`
+/// or an `Expression::OutputSubstitution`.
+///
+/// # Arguments
+/// - `token`: The token to parse.
+///
+/// # Returns
+/// - `Ok(Expression)` if the token is a process substitution and its source parses.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_process_substitution(token: &Token) -> Result<Expression> {
+    match token {
+        Token::InputSubstitution(source) => Parser::new(source.as_str())
+            .parse()
+            .map(|statement| Expression::InputSubstitution(Box::new(statement))),
+        Token::OutputSubstitution(source) => Parser::new(source.as_str())
+            .parse()
+            .map(|statement| Expression::OutputSubstitution(Box::new(statement))),
+        _ => Err(Error::NOT_IMPLEMENTED),
+    }
+}
+
+/// Parses an arithmetic substitution token (e.g., `$((1 + 2))`) into an `Expression::Arithmetic`.
+///
+/// # Arguments
+/// - `token`: The token to parse.
+///
+/// # Returns
+/// - `Ok(Expression::Arithmetic)` if the token is `Token::Arithmetic` and its source is a valid expression.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_arithmetic_substitution(token: &Token) -> Result<Expression> {
+    let Token::Arithmetic(source) = token else {
+        Err(Error::NOT_IMPLEMENTED)?
+    };
+
+    let mut lexer = Lexer::new(source.as_str());
+
+    let mut tokens = Vec::new();
+
+    loop {
+        match lexer.next()? {
+            Token::EOF => break,
+            token => tokens.push(token),
+        }
+    }
+
+    arithmetic::parse_arithmetic(&tokens)
+        .map(|arithmetic| Expression::Arithmetic(Box::new(arithmetic)))
+}
+
+/// Parses a braced parameter expansion token into an `Expression::Parameter`.
+///
+/// # Arguments
+/// - `token`: The token to parse.
+///
+/// # Returns
+/// - `Ok(Expression::Parameter)` if the token is `Token::Parameter` with a known operator.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_parameter(token: &Token) -> Result<Expression> {
+    let Token::Parameter(name, operator, operand) = token else {
+        Err(Error::NOT_IMPLEMENTED)?
+    };
+
+    // `${#}` is the special parameter `#`, not the length of an empty name.
+    if let Some(name) = name.strip_prefix('#').filter(|name| !name.is_empty()) {
+        return Ok(Expression::Parameter(Parameter::new(
+            name.to_string(),
+            ParameterOperator::Length,
+        )));
+    }
+
+    let word = parse_word_parts(operand)?;
+
+    let is_null_checked = operator.starts_with(':');
+
+    let operator = match operator.trim_start_matches(':') {
+        "" if is_null_checked => parse_substring(operand)?,
+        "" => ParameterOperator::Value,
+        "-" => ParameterOperator::Default {
+            word,
+            is_null_checked,
+        },
+        "=" => ParameterOperator::Assign {
+            word,
+            is_null_checked,
+        },
+        "?" => ParameterOperator::Error {
+            word,
+            is_null_checked,
+        },
+        "+" => ParameterOperator::Alternative {
+            word,
+            is_null_checked,
+        },
+        "#" | "##" => ParameterOperator::RemovePrefix {
+            pattern: word,
+            is_longest: operator == "##",
+        },
+        "%" | "%%" => ParameterOperator::RemoveSuffix {
+            pattern: word,
+            is_longest: operator == "%%",
+        },
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    Ok(Expression::Parameter(Parameter::new(
+        name.to_string(),
+        operator,
+    )))
+}
+
+/// Parses the `offset[:length]` operand of a substring expansion (e.g., `${VAR:1:3}`).
+fn parse_substring(operand: &[Token]) -> Result<ParameterOperator> {
+    let operand = match operand {
+        [Token::String(string)] => string.as_str(),
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    let (offset, length) = match operand.split_once(':') {
+        Some((offset, length)) => (offset, Some(length)),
+        None => (operand, None),
+    };
+
+    let offset = offset
+        .trim()
+        .parse::<isize>()
+        .map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+    let length = length
+        .map(|length| length.trim().parse::<isize>())
+        .transpose()
+        .map_err(|_| Error::NOT_IMPLEMENTED)?;
+
+    Ok(ParameterOperator::Substring { offset, length })
+}
+
+/// Parses the parts of a word: strings, quoted text, identifiers, indexes, parameter expansions,
+/// command substitutions, or arithmetic substitutions.
+fn parse_word_parts(parts: &[Token]) -> Result<Vec<Expression>> {
+    parts
+        .iter()
+        .map(|part| {
+            parse_string(part)
+                .or_else(|_| parse_quoted(part))
+                .or_else(|_| parse_identifier(part))
+                .or_else(|_| parse_index(part))
+                .or_else(|_| parse_parameter(part))
+                .or_else(|_| parse_substitution(part))
+                .or_else(|_| parse_arithmetic_substitution(part))
+        })
+        .collect()
+}
+
+/// Parses a word token into an `Expression::Word`.
+///
+/// Each part of the word is parsed as a string, quoted text, identifier, index,
+/// parameter expansion, command substitution, or arithmetic substitution.
+///
+/// # Arguments
+/// - `token`: The token to parse.
+///
+/// # Returns
+/// - `Ok(Expression::Word)` if the token is `Token::Word` and all of its parts parse.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_word(token: &Token) -> Result<Expression> {
+    match token {
+        Token::Word(parts) => parse_word_parts(parts).map(Expression::Word),
+        _ => Err(Error::NOT_IMPLEMENTED),
+    }
+}
+
 /// Parses the value part of an assignment expression.
 ///
-/// Tries to parse the token as a `null`, string, boolean, number,
-/// or file descriptor in that order.
+/// Tries to parse the token as a `null`, string, boolean, number, file descriptor, identifier,
+/// index, command substitution, arithmetic substitution, parameter expansion, or word in that order.
 ///
 /// # Arguments
 /// - `token`: The token to parse.
@@ -112,6 +336,12 @@ fn parse_assignment_value(token: &Token) -> Result<Expression> {
         .or_else(|_| parse_boolean(token))
         .or_else(|_| parse_number(token))
         .or_else(|_| parse_file_descriptor(token))
+        .or_else(|_| parse_identifier(token))
+        .or_else(|_| parse_index(token))
+        .or_else(|_| parse_substitution(token))
+        .or_else(|_| parse_arithmetic_substitution(token))
+        .or_else(|_| parse_parameter(token))
+        .or_else(|_| parse_word(token))
 }
 
 /// Parses an assignment expression from three tokens: `<identifier> = <value>`.
@@ -131,9 +361,218 @@ pub fn parse_assignment(tokens: &[Token; 3]) -> Result<Assignment> {
     Ok(Assignment::new(identifier, value))
 }
 
+/// Removes a leading `open` and a trailing `close` character from a sequence of tokens,
+/// e.g. the brackets of `[a b c]` lexed as `[a`, `b`, `c]`.
+///
+/// Standalone brace tokens are removed as well, and tokens left empty are dropped.
+/// A word left with a single unquoted part is replaced by that part; quoted text stays a word.
+fn strip_delimiters(tokens: &[Token], open: char, close: char) -> Result<Vec<Token>> {
+    let mut tokens = tokens.to_vec();
+
+    let first = tokens.first_mut().ok_or(Error::NOT_IMPLEMENTED)?;
+
+    match first {
+        Token::LeftBrace if open == '{' => *first = Token::String(String::new()),
+        Token::String(string) if string.starts_with(open) => {
+            string.remove(0);
+        }
+        Token::Word(parts) => match parts.first_mut() {
+            Some(Token::String(string)) if string.starts_with(open) => {
+                string.remove(0);
+            }
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        },
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    }
+
+    let last = tokens.last_mut().ok_or(Error::NOT_IMPLEMENTED)?;
+
+    match last {
+        Token::RightBrace if close == '}' => *last = Token::String(String::new()),
+        Token::String(string) if string.ends_with(close) => {
+            string.pop();
+        }
+        Token::Word(parts) => match parts.last_mut() {
+            Some(Token::String(string)) if string.ends_with(close) => {
+                string.pop();
+            }
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        },
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    }
+
+    let is_empty = |token: &Token| matches!(token, Token::String(string) if string.is_empty());
+
+    Ok(tokens
+        .into_iter()
+        .map(|token| match token {
+            // a word left with a single part (e.g., `$HOME]`) is that part,
+            // unless it is quoted (e.g., `"b c"]`).
+            Token::Word(mut parts) => {
+                parts.retain(|part| !is_empty(part));
+
+                match parts.as_slice() {
+                    [Token::Quoted(_)] => Token::Word(parts),
+                    [_] => parts.remove(0),
+                    _ => Token::Word(parts),
+                }
+            }
+            token => token,
+        })
+        .filter(|token| !is_empty(token))
+        .collect())
+}
+
+/// Parses a list value: `[item item ...]`.
+///
+/// # Returns
+/// - `Ok(Expression::List)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_list(tokens: &[Token]) -> Result<Expression> {
+    strip_delimiters(tokens, '[', ']')?
+        .iter()
+        .map(parse_argument)
+        .collect::<Result<Vec<_>>>()
+        .map(Expression::List)
+}
+
+/// Parses a map value: `{key: value, key: value ...}`, where the commas are optional.
+///
+/// # Returns
+/// - `Ok(Expression::Map)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_map(tokens: &[Token]) -> Result<Expression> {
+    let tokens = strip_delimiters(tokens, '{', '}')?;
+
+    let mut entries = Vec::new();
+
+    let mut iter = tokens.iter().filter(|token| !is_keyword(token, ","));
+
+    while let Some(token) = iter.next() {
+        let (key, mut value) = split_map_key(token)?;
+
+        // the value is either attached (`key:value`) or the next token (`key: value`).
+        let value = match value.as_slice() {
+            [] => iter.next().ok_or(Error::NOT_IMPLEMENTED)?.clone(),
+            [Token::String(_)] => value.remove(0),
+            _ => Token::Word(value),
+        };
+
+        let value = match value {
+            Token::String(string) => {
+                Token::String(string.strip_suffix(',').map(String::from).unwrap_or(string))
+            }
+            Token::Word(mut parts) => {
+                if let Some(Token::String(string)) = parts.last_mut() {
+                    if string.ends_with(',') {
+                        string.pop();
+                    }
+
+                    if string.is_empty() {
+                        parts.pop();
+                    }
+                }
+
+                Token::Word(parts)
+            }
+            value => value,
+        };
+
+        if key.is_empty() {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        entries.push((key, parse_argument(&value)?));
+    }
+
+    Ok(Expression::Map(entries))
+}
+
+/// Splits the key of a map entry at its first unquoted colon: `key:`, `key:value` or `"key": value`.
+///
+/// # Returns
+/// - `Ok((String, Vec<Token>))` with the key and the parts of the value attached to it.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the key is not literal text followed by a colon.
+fn split_map_key(token: &Token) -> Result<(String, Vec<Token>)> {
+    let parts = match token {
+        Token::Word(parts) => parts.as_slice(),
+        token => std::slice::from_ref(token),
+    };
+
+    let mut key = String::new();
+
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            Token::String(string) => match string.split_once(':') {
+                Some((left, right)) => {
+                    key.push_str(left);
+
+                    let mut value = Vec::with_capacity(parts.len() - i);
+
+                    if !right.is_empty() {
+                        value.push(Token::String(right.to_string()));
+                    }
+
+                    value.extend_from_slice(&parts[i + 1..]);
+
+                    return Ok((key, value));
+                }
+                None => key.push_str(string),
+            },
+            Token::Quoted(string) => key.push_str(string),
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        }
+    }
+
+    Err(Error::NOT_IMPLEMENTED)
+}
+
+/// Parses an assignment of a list or a map: `<identifier> = [a b c]` or `<identifier> = {k: v}`.
+///
+/// # Returns
+/// - `Ok(Assignment)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the syntax is invalid.
+pub fn parse_collection_assignment(tokens: &[Token]) -> Result<Assignment> {
+    let [identifier, Token::Equal, value @ ..] = tokens else {
+        Err(Error::NOT_IMPLEMENTED)?
+    };
+
+    let identifier = parse_identifier(identifier)?;
+
+    let value = parse_list(value).or_else(|_| parse_map(value))?;
+
+    Ok(Assignment::new(identifier, value))
+}
+
+/// Parses an assignment whose value is an arithmetic expression: `<identifier> = <expression>`,
+/// e.g. `$n = $n + 1`.
+///
+/// # Returns
+/// - `Ok(Assignment)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the syntax is invalid.
+pub fn parse_arithmetic_assignment(tokens: &[Token]) -> Result<Assignment> {
+    let [identifier, Token::Equal, value @ ..] = tokens else {
+        Err(Error::NOT_IMPLEMENTED)?
+    };
+
+    if value.len() < 2 {
+        Err(Error::NOT_IMPLEMENTED)?
+    }
+
+    let identifier = parse_identifier(identifier)?;
+
+    let value = arithmetic::parse_arithmetic(value)?;
+
+    Ok(Assignment::new(
+        identifier,
+        Expression::Arithmetic(Box::new(value)),
+    ))
+}
+
 /// Parses the right-hand side of a redirection operator (`>` or `<`).
 ///
-/// Accepts strings, identifiers, numbers, or file descriptors.
+/// Accepts strings, identifiers, indexes, numbers, file descriptors, parameter expansions,
+/// process substitutions (e.g., `< <(ls)`), or words.
 ///
 /// # Returns
 /// - `Ok(Expression)` if successfully parsed.
@@ -141,11 +580,15 @@ pub fn parse_assignment(tokens: &[Token; 3]) -> Result<Assignment> {
 fn parse_redirect_right(token: &Token) -> Result<Expression> {
     parse_string(token)
         .or_else(|_| parse_identifier(token))
+        .or_else(|_| parse_index(token))
         .or_else(|_| parse_number(token))
         .or_else(|_| parse_file_descriptor(token))
+        .or_else(|_| parse_parameter(token))
+        .or_else(|_| parse_process_substitution(token))
+        .or_else(|_| parse_word(token))
 }
 
-/// Parses an abbreviated redirect form like `> file` or `< file`.
+/// Parses an abbreviated redirect form like `> file`, `>> file` or `< file`.
 ///
 /// # Arguments
 /// - `tokens`: An array of exactly two tokens.
@@ -156,7 +599,20 @@ fn parse_redirect_right(token: &Token) -> Result<Expression> {
 fn parse_abbreviated_redirect(tokens: &[Token; 2]) -> Result<Redirect> {
     let (left, operator) = match tokens[0] {
         Token::GreaterThan => (Expression::FileDescriptor(1), RedirectOperator::GreaterThan),
+        Token::DoubleGreaterThan => (
+            Expression::FileDescriptor(1),
+            RedirectOperator::DoubleGreaterThan,
+        ),
+        Token::GreaterThanPipe => (
+            Expression::FileDescriptor(1),
+            RedirectOperator::GreaterThanPipe,
+        ),
         Token::LessThan => (Expression::FileDescriptor(0), RedirectOperator::LessThan),
+        Token::LessThanGreaterThan => (
+            Expression::FileDescriptor(0),
+            RedirectOperator::LessThanGreaterThan,
+        ),
+        Token::TripleLessThan => (Expression::FileDescriptor(0), RedirectOperator::HereString),
         _ => Err(Error::NOT_IMPLEMENTED)?,
     };
     let right = parse_redirect_right(&tokens[1])?;
@@ -174,7 +630,11 @@ fn parse_abbreviated_redirect(tokens: &[Token; 2]) -> Result<Redirect> {
 fn parse_normal_redirect(tokens: &[Token; 3]) -> Result<Redirect> {
     let operator = match tokens[1] {
         Token::GreaterThan => RedirectOperator::GreaterThan,
+        Token::DoubleGreaterThan => RedirectOperator::DoubleGreaterThan,
+        Token::GreaterThanPipe => RedirectOperator::GreaterThanPipe,
         Token::LessThan => RedirectOperator::LessThan,
+        Token::LessThanGreaterThan => RedirectOperator::LessThanGreaterThan,
+        Token::TripleLessThan => RedirectOperator::HereString,
         _ => Err(Error::NOT_IMPLEMENTED)?,
     };
     let left = parse_file_descriptor(&tokens[0])?;
@@ -182,6 +642,25 @@ fn parse_normal_redirect(tokens: &[Token; 3]) -> Result<Redirect> {
     Ok(Redirect::new(operator, left, right))
 }
 
+/// Parses a here-document token into a redirect of the given file descriptor.
+///
+/// The body becomes an `Expression::String` if it has no expansions, otherwise an `Expression::Word`.
+///
+/// # Returns
+/// - `Ok(Redirect)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+pub fn parse_heredoc(left: Expression, token: &Token) -> Result<Redirect> {
+    let right = match token {
+        Token::HereDocument(parts) => match parts.as_slice() {
+            [part] => parse_string(part)?,
+            parts => Expression::Word(parse_word_parts(parts)?),
+        },
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    Ok(Redirect::new(RedirectOperator::HereDocument, left, right))
+}
+
 /// Parses a redirection from a slice of tokens.
 ///
 /// Automatically detects whether the redirect is abbreviated or normal based on token count.
@@ -205,10 +684,15 @@ pub fn parse_redirect(tokens: &[Token]) -> Result<Redirect> {
 
 /// Parses the command name token into an expression.
 ///
-/// Accepts strings, identifiers, or numbers.
+/// Accepts strings, identifiers, numbers, booleans (e.g., the `true` and `false` commands),
+/// command substitutions, parameter expansions, or words.
 fn parse_command_name(token: &Token) -> Result<Expression> {
     parse_string(token)
         .or(parse_identifier(token).or(parse_number(token)))
+        .or(parse_boolean(token))
+        .or_else(|_| parse_substitution(token))
+        .or_else(|_| parse_parameter(token))
+        .or_else(|_| parse_word(token))
         .or_else(|_| Err(Error::NOT_IMPLEMENTED))
 }
 
@@ -242,16 +726,49 @@ fn parse_command_arguments(
         }
 
         match token {
-            Token::GreaterThan | Token::LessThan => {
+            Token::GreaterThan
+            | Token::DoubleGreaterThan
+            | Token::GreaterThanPipe
+            | Token::LessThan
+            | Token::LessThanGreaterThan
+            | Token::TripleLessThan => {
                 redirects.push(parse_redirect(&tokens[i..i + 2])?);
                 skip_count = 1;
             }
 
+            Token::HereDocument(_) => {
+                redirects.push(parse_heredoc(Expression::FileDescriptor(0), token)?);
+            }
+
+            Token::FileDescriptor(_)
+                if matches!(tokens.get(i + 1), Some(Token::HereDocument(_))) =>
+            {
+                redirects.push(parse_heredoc(
+                    parse_file_descriptor(token)?,
+                    &tokens[i + 1],
+                )?);
+                skip_count = 1;
+            }
+
             Token::FileDescriptor(_) => {
                 redirects.push(parse_redirect(&tokens[i..i + 3])?);
                 skip_count = 2;
             }
 
+            // a standalone `=` is an argument, e.g. `test $a = b`.
+            Token::Equal => {
+                arguments.push(Expression::String(String::from("=")));
+            }
+
+            // so are parentheses, e.g. `[ ( $a = b ) ]`.
+            Token::LeftParen => {
+                arguments.push(Expression::String(String::from("(")));
+            }
+
+            Token::RightParen => {
+                arguments.push(Expression::String(String::from(")")));
+            }
+
             Token::Ampersand => {
                 if i == len - 1 {
                     is_background = Expression::Boolean(true);
@@ -261,11 +778,7 @@ fn parse_command_arguments(
                 }
             }
             _ => {
-                arguments.push(
-                    parse_number(token)
-                        .or(parse_identifier(token).or(parse_string(token)))
-                        .or_else(|_| Err(Error::NOT_IMPLEMENTED))?,
-                );
+                arguments.push(parse_argument(token)?);
             }
         }
     }
@@ -273,16 +786,129 @@ fn parse_command_arguments(
     Ok((arguments, redirects, is_background))
 }
 
+/// Parses a single argument token: a number, identifier, index, string, boolean,
+/// command substitution, process substitution, arithmetic substitution, parameter expansion,
+/// or word.
+fn parse_argument(token: &Token) -> Result<Expression> {
+    parse_number(token)
+        .or(parse_identifier(token).or(parse_string(token)))
+        .or(parse_boolean(token))
+        .or_else(|_| parse_index(token))
+        .or_else(|_| parse_substitution(token))
+        .or_else(|_| parse_process_substitution(token))
+        .or_else(|_| parse_arithmetic_substitution(token))
+        .or_else(|_| parse_parameter(token))
+        .or_else(|_| parse_word(token))
+}
+
+/// Splits a `NAME=value` token into an assignment, if it starts with a valid variable name.
+///
+/// The value is the rest of the token: a string, or a word when it is quoted or expanded
+/// (e.g., `A="x y"` or `A=$HOME/bin`).
+///
+/// # Returns
+/// - `Ok(Assignment)` if the token is an assignment.
+/// - `Err(Error::NOT_IMPLEMENTED)` otherwise.
+fn parse_prefix_assignment(token: &Token) -> Result<Assignment> {
+    let (first, rest) = match token {
+        Token::String(string) => (string, &[][..]),
+        Token::Word(parts) => match parts.split_first() {
+            Some((Token::String(string), rest)) => (string, rest),
+            _ => Err(Error::NOT_IMPLEMENTED)?,
+        },
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    let (name, value) = first.split_once('=').ok_or(Error::NOT_IMPLEMENTED)?;
+
+    let mut chars = name.chars();
+
+    let is_name = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !is_name {
+        Err(Error::NOT_IMPLEMENTED)?
+    }
+
+    let mut parts = Vec::with_capacity(rest.len() + 1);
+
+    if !value.is_empty() {
+        parts.push(Token::String(value.to_string()));
+    }
+
+    parts.extend_from_slice(rest);
+
+    let value = match parts.as_slice() {
+        [] => Expression::String(String::new()),
+        [Token::String(string)] => Expression::String(string.to_string()),
+        _ => parse_word(&Token::Word(parts))?,
+    };
+
+    Ok(Assignment::new(
+        Expression::Identifier(name.to_string()),
+        value,
+    ))
+}
+
+/// Parses the `NAME=value` assignments before a command name (e.g., `RUST_LOG=debug cargo run`).
+///
+/// `$NAME = value` is not a prefix, so that an assignment followed by extra tokens
+/// (e.g., `$s = hello world`) is a syntax error.
+///
+/// # Returns
+/// The assignments and the number of tokens they take. At least one token is left for the name.
+fn parse_command_assignments(tokens: &[Token]) -> (Vec<Assignment>, usize) {
+    let mut assignments = Vec::new();
+
+    // the last token is left for the name.
+    for token in tokens.iter().take(tokens.len().saturating_sub(1)) {
+        match parse_prefix_assignment(token) {
+            Ok(assignment) => assignments.push(assignment),
+            Err(_) => break,
+        }
+    }
+
+    let index = assignments.len();
+
+    (assignments, index)
+}
+
 /// Parses a full command from a sequence of tokens.
 ///
-/// The first token is treated as the command name, and the remainder
+/// The assignments before the command name are parsed first (see `parse_command_assignments`).
+/// The next token is treated as the command name, and the remainder
 /// is parsed into arguments, redirects, and background execution flag.
+/// A brace group or a subshell takes the place of the name (see `parse_group`).
 pub fn parse_command(tokens: &[Token]) -> Result<Command> {
-    let name = parse_command_name(&tokens[0])?;
+    if matches!(tokens.first(), Some(Token::LeftBrace | Token::LeftParen)) {
+        return parse_group(tokens);
+    }
+
+    // an assignment with extra tokens (e.g., `$s = hello world`) is not a command.
+    if matches!(tokens, [Token::Identifier(_), Token::Equal, ..]) {
+        Err(Error::new(
+            ErrorKind::Other,
+            "syntax error: assignment takes a single value, quote it",
+        ))?
+    }
+
+    let (assignments, index) = parse_command_assignments(tokens);
+
+    let tokens = &tokens[index..];
+
+    let name = parse_command_name(tokens.first().ok_or(Error::NOT_IMPLEMENTED)?)?;
 
     let (arguments, redirects, is_background) = parse_command_arguments(&tokens[1..])?;
 
-    Ok(Command::new(name, arguments, redirects, is_background))
+    Ok(Command::new(
+        assignments,
+        name,
+        arguments,
+        redirects,
+        is_background,
+    ))
 }
 
 /// Parses a pipeline (`|`-separated commands) from tokens.
@@ -307,16 +933,342 @@ pub fn parse_pipe(tokens: &[Token]) -> Result<Pipe> {
     Ok(pipe)
 }
 
-fn split(place: &Token, tokens: &[Token]) -> (Vec<Token>, Vec<Token>) {
-    if let Some(pos) = tokens.iter().position(|t| t == place) {
-        (tokens[..pos].to_vec(), tokens[pos + 1..].to_vec())
-    } else {
-        (tokens.to_vec(), Vec::new())
-    }
-}
+/// Parses a single command or a pipeline, depending on whether the tokens contain a pipe.
+///
+/// Compound statements (`if`, `while`, `for`, `fn`) and the `break`, `continue`
+/// and `return` keywords are accepted in place of a single command.
+fn parse_pipeline(tokens: &[Token]) -> Result<Statement> {
+    match tokens {
+        [token, ..] if is_keyword(token, "if") => parse_if(tokens).map(Statement::If),
 
-fn recursion_split(place: &Token, tokens: &[Token]) -> Vec<Vec<Token>> {
-    let (left, right) = split(place, tokens);
+        [token, ..] if is_keyword(token, "while") => parse_while(tokens).map(Statement::While),
+
+        [token, ..] if is_keyword(token, "for") => parse_for(tokens).map(Statement::For),
+
+        [token] if is_keyword(token, "break") => Ok(Statement::Break),
+
+        [token] if is_keyword(token, "continue") => Ok(Statement::Continue),
+
+        [token, ..] if is_keyword(token, "fn") => parse_function(tokens).map(Statement::Function),
+
+        [token] if is_keyword(token, "return") => Ok(Statement::Return(None)),
+
+        [token, status] if is_keyword(token, "return") => parse_number(status)
+            .or_else(|_| parse_identifier(status))
+            .map(|status| Statement::Return(Some(status))),
+
+        _ if !top_level_positions(tokens, |token| token == &Token::Pipe).is_empty() => {
+            parse_pipe(tokens).map(Statement::Pipe)
+        }
+
+        _ => parse_command(tokens).map(Statement::Command),
+    }
+}
+
+/// Parses an AND/OR list (`&&`/`||`-separated pipelines) from tokens.
+///
+/// The list is split at the last logical operator so that the resulting
+/// tree is left-associative: `a && b || c` becomes `(a && b) || c`.
+///
+/// # Returns
+/// - `Ok(Logical)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if no logical operator is found or either side fails to parse.
+pub fn parse_logical(tokens: &[Token]) -> Result<Logical> {
+    let position = top_level_positions(tokens, |token| {
+        matches!(token, Token::DoubleAmpersand | Token::DoublePipe)
+    })
+    .pop()
+    .ok_or(Error::NOT_IMPLEMENTED)?;
+
+    let operator = match tokens[position] {
+        Token::DoubleAmpersand => LogicalOperator::And,
+        _ => LogicalOperator::Or,
+    };
+
+    let left = parse_logical(&tokens[..position])
+        .map(Statement::Logical)
+        .or_else(|_| parse_pipeline(&tokens[..position]))?;
+
+    let right = parse_pipeline(&tokens[position + 1..])?;
+
+    Ok(Logical::new(operator, left, right))
+}
+
+/// Parses a block enclosed in braces (`{ ... }`) into a `Statement::Sequence`.
+///
+/// Statements inside the block are separated by semicolons; empty statements are skipped.
+///
+/// # Returns
+/// - `Ok(Statement::Sequence)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the tokens are not a single block or a statement fails to parse.
+pub fn parse_block(tokens: &[Token]) -> Result<Statement> {
+    if tokens.first() != Some(&Token::LeftBrace)
+        || find_block_end(tokens, 0) != Some(tokens.len() - 1)
+    {
+        Err(Error::NOT_IMPLEMENTED)?
+    }
+
+    parse_sequence(&tokens[1..tokens.len() - 1])
+}
+
+/// Parses the semicolon-separated statements of a block or a subshell into a `Statement::Sequence`.
+fn parse_sequence(tokens: &[Token]) -> Result<Statement> {
+    let mut sequence = Sequence::new();
+
+    for tokens in recursion_split(&Token::Semicolon, tokens) {
+        if tokens.is_empty() {
+            continue;
+        }
+
+        sequence.push_back(parse_statement(&tokens)?);
+    }
+
+    Ok(Statement::Sequence(sequence))
+}
+
+/// Parses a brace group (`{ ... }`) or a subshell (`( ... )`) used as a command,
+/// followed by its redirects and an optional `&`, e.g. `{ date; uptime; } > log.txt`.
+///
+/// The group is the name of the returned command, as an `Expression::Group`
+/// or an `Expression::Subshell`.
+///
+/// # Returns
+/// - `Ok(Command)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the tokens do not start with a group, or arguments follow it.
+pub fn parse_group(tokens: &[Token]) -> Result<Command> {
+    let end = match tokens.first() {
+        Some(Token::LeftBrace | Token::LeftParen) => {
+            find_block_end(tokens, 0).ok_or(Error::NOT_IMPLEMENTED)?
+        }
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    let body = Box::new(parse_sequence(&tokens[1..end])?);
+
+    let name = match (&tokens[0], &tokens[end]) {
+        (Token::LeftBrace, Token::RightBrace) => Expression::Group(body),
+        (Token::LeftParen, Token::RightParen) => Expression::Subshell(body),
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    let (arguments, redirects, is_background) = parse_command_arguments(&tokens[end + 1..])?;
+
+    if !arguments.is_empty() {
+        Err(Error::NOT_IMPLEMENTED)?
+    }
+
+    Ok(Command::new(
+        Vec::new(),
+        name,
+        arguments,
+        redirects,
+        is_background,
+    ))
+}
+
+/// Parses a conditional block: `if <condition> { ... }`, optionally followed by
+/// `else { ... }` or `else if ...`.
+///
+/// The condition may be a command, a pipeline, or an AND/OR list.
+///
+/// # Returns
+/// - `Ok(If)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the syntax is invalid.
+pub fn parse_if(tokens: &[Token]) -> Result<If> {
+    if !tokens.first().is_some_and(|token| is_keyword(token, "if")) {
+        Err(Error::NOT_IMPLEMENTED)?
+    }
+
+    let start = tokens
+        .iter()
+        .position(|token| token == &Token::LeftBrace)
+        .ok_or(Error::NOT_IMPLEMENTED)?;
+
+    let end = find_block_end(tokens, start).ok_or(Error::NOT_IMPLEMENTED)?;
+
+    let condition = parse_condition(&tokens[1..start])?;
+
+    let consequence = parse_block(&tokens[start..=end])?;
+
+    let alternative = match &tokens[end + 1..] {
+        [] => None,
+
+        [token, rest @ ..] if is_keyword(token, "else") => Some(
+            parse_if(rest)
+                .map(Statement::If)
+                .or_else(|_| parse_block(rest))?,
+        ),
+
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    Ok(If::new(condition, consequence, alternative))
+}
+
+/// Parses a `while` loop: `while <condition> { ... }`.
+///
+/// # Returns
+/// - `Ok(While)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the syntax is invalid.
+pub fn parse_while(tokens: &[Token]) -> Result<While> {
+    if !tokens
+        .first()
+        .is_some_and(|token| is_keyword(token, "while"))
+    {
+        Err(Error::NOT_IMPLEMENTED)?
+    }
+
+    let start = tokens
+        .iter()
+        .position(|token| token == &Token::LeftBrace)
+        .ok_or(Error::NOT_IMPLEMENTED)?;
+
+    let condition = parse_condition(&tokens[1..start])?;
+
+    let body = parse_block(&tokens[start..])?;
+
+    Ok(While::new(condition, body))
+}
+
+/// Parses a `for` loop: `for $<identifier> in <items> { ... }`.
+///
+/// The items are parsed like command arguments, so they are expanded (e.g., globbed) at execution time.
+///
+/// # Returns
+/// - `Ok(For)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the syntax is invalid.
+pub fn parse_for(tokens: &[Token]) -> Result<For> {
+    let (identifier, rest) = match tokens {
+        [token, identifier, keyword, rest @ ..]
+            if is_keyword(token, "for") && is_keyword(keyword, "in") =>
+        {
+            (parse_identifier(identifier)?, rest)
+        }
+
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    let start = rest
+        .iter()
+        .position(|token| token == &Token::LeftBrace)
+        .ok_or(Error::NOT_IMPLEMENTED)?;
+
+    let items = rest[..start]
+        .iter()
+        .map(parse_argument)
+        .collect::<Result<Vec<Expression>>>()?;
+
+    let body = parse_block(&rest[start..])?;
+
+    Ok(For::new(identifier, items, body))
+}
+
+/// Parses a function definition: `fn <name> { ... }`.
+///
+/// # Returns
+/// - `Ok(Function)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if the syntax is invalid.
+pub fn parse_function(tokens: &[Token]) -> Result<Function> {
+    let (name, rest) = match tokens {
+        [token, name, rest @ ..] if is_keyword(token, "fn") => (parse_string(name)?, rest),
+        _ => Err(Error::NOT_IMPLEMENTED)?,
+    };
+
+    let body = parse_block(rest)?;
+
+    Ok(Function::new(name, body))
+}
+
+/// Parses the condition of an `if` or `while`: a command, a pipeline, or an AND/OR list.
+fn parse_condition(tokens: &[Token]) -> Result<Statement> {
+    parse_logical(tokens)
+        .map(Statement::Logical)
+        .or_else(|_| parse_pipeline(tokens))
+}
+
+/// Parses any single statement: a compound statement, an AND/OR list,
+/// an assignment, a pipeline, or a command.
+///
+/// # Returns
+/// - `Ok(Statement)` if successfully parsed.
+/// - `Err(Error::NOT_IMPLEMENTED)` if no statement form matches.
+pub fn parse_statement(tokens: &[Token]) -> Result<Statement> {
+    parse_logical(tokens)
+        .map(Statement::Logical)
+        .or_else(|_| parse_collection_assignment(tokens).map(Statement::Assignment))
+        .or_else(|_| {
+            TryInto::<&[Token; 3]>::try_into(tokens)
+                .map_err(|_| Error::NOT_IMPLEMENTED)
+                .and_then(parse_assignment)
+                .map(Statement::Assignment)
+        })
+        .or_else(|_| parse_arithmetic_assignment(tokens).map(Statement::Assignment))
+        .or_else(|_| parse_pipeline(tokens))
+}
+
+/// Returns `true` if the token is the given keyword (an unquoted word such as `if` or `else`).
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+    matches!(token, Token::String(string) if string == keyword)
+}
+
+/// Returns the position of the right brace (or parenthesis) matching the left one at `start`.
+fn find_block_end(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match token {
+            Token::LeftBrace | Token::LeftParen => depth += 1,
+
+            Token::RightBrace | Token::RightParen => {
+                depth = depth.checked_sub(1)?;
+
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns the positions of the tokens matching `predicate`, skipping tokens nested in blocks
+/// and subshells.
+fn top_level_positions<F>(tokens: &[Token], predicate: F) -> Vec<usize>
+where
+    F: Fn(&Token) -> bool,
+{
+    let mut depth = 0usize;
+
+    let mut positions = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LeftBrace | Token::LeftParen => depth += 1,
+
+            Token::RightBrace | Token::RightParen => depth = depth.saturating_sub(1),
+
+            _ if depth == 0 && predicate(token) => positions.push(i),
+
+            _ => {}
+        }
+    }
+
+    positions
+}
+
+fn split(place: &Token, tokens: &[Token]) -> (Vec<Token>, Vec<Token>) {
+    if let Some(&pos) = top_level_positions(tokens, |t| t == place).first() {
+        (tokens[..pos].to_vec(), tokens[pos + 1..].to_vec())
+    } else {
+        (tokens.to_vec(), Vec::new())
+    }
+}
+
+fn recursion_split(place: &Token, tokens: &[Token]) -> Vec<Vec<Token>> {
+    let (left, right) = split(place, tokens);
 
     if right.is_empty() {
         vec![left]
@@ -476,6 +1428,127 @@ mod tests {
         assert!(parse_file_descriptor(&Token::Number(2)).is_err());
     }
 
+    #[test]
+    fn test_parse_substitution() {
+        let expression = parse_substitution(&Token::Substitution("pwd".to_string())).unwrap();
+
+        let mut sequence = Sequence::new();
+
+        sequence.push_back(Statement::Command(Command::new(
+            Vec::new(),
+            Expression::String("pwd".to_string()),
+            Vec::new(),
+            Vec::new(),
+            Expression::Boolean(false),
+        )));
+
+        assert_eq!(
+            expression,
+            Expression::Substitution(Box::new(Statement::Sequence(sequence)))
+        );
+
+        assert!(parse_substitution(&Token::String("pwd".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_parse_process_substitution() {
+        let mut sequence = Sequence::new();
+
+        sequence.push_back(Statement::Command(Command::new(
+            Vec::new(),
+            Expression::String("ls".to_string()),
+            Vec::new(),
+            Vec::new(),
+            Expression::Boolean(false),
+        )));
+
+        let statement = Box::new(Statement::Sequence(sequence));
+
+        assert_eq!(
+            parse_process_substitution(&Token::InputSubstitution("ls".to_string())).unwrap(),
+            Expression::InputSubstitution(statement.clone())
+        );
+
+        assert_eq!(
+            parse_process_substitution(&Token::OutputSubstitution("ls".to_string())).unwrap(),
+            Expression::OutputSubstitution(statement)
+        );
+
+        assert!(parse_process_substitution(&Token::Substitution("ls".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_parse_parameter() {
+        let token = Token::Parameter(
+            "X".to_string(),
+            ":-".to_string(),
+            vec![Token::String("/root".to_string())],
+        );
+
+        assert_eq!(
+            parse_parameter(&token).unwrap(),
+            Expression::Parameter(Parameter::new(
+                "X".to_string(),
+                ParameterOperator::Default {
+                    word: vec![Expression::String("/root".to_string())],
+                    is_null_checked: true,
+                }
+            ))
+        );
+
+        let token = Token::Parameter("#X".to_string(), "".to_string(), vec![]);
+
+        assert_eq!(
+            parse_parameter(&token).unwrap(),
+            Expression::Parameter(Parameter::new("X".to_string(), ParameterOperator::Length))
+        );
+
+        let token = Token::Parameter(
+            "X".to_string(),
+            ":".to_string(),
+            vec![Token::String(" -3:2".to_string())],
+        );
+
+        assert_eq!(
+            parse_parameter(&token).unwrap(),
+            Expression::Parameter(Parameter::new(
+                "X".to_string(),
+                ParameterOperator::Substring {
+                    offset: -3,
+                    length: Some(2),
+                }
+            ))
+        );
+
+        let token = Token::Parameter(
+            "X".to_string(),
+            ":".to_string(),
+            vec![Token::String("a".to_string())],
+        );
+
+        assert!(parse_parameter(&token).is_err());
+    }
+
+    #[test]
+    fn test_parse_word() {
+        let token = Token::Word(vec![
+            Token::Identifier("HOME".to_string()),
+            Token::String("/bin".to_string()),
+        ]);
+
+        assert_eq!(
+            parse_word(&token).unwrap(),
+            Expression::Word(vec![
+                Expression::Identifier("HOME".to_string()),
+                Expression::String("/bin".to_string()),
+            ])
+        );
+
+        assert!(parse_word(&Token::Word(vec![Token::Number(1)])).is_err());
+
+        assert!(parse_word(&Token::String("/bin".to_string())).is_err());
+    }
+
     #[test]
     fn test_parse_assignment() {
         let tokens = [
@@ -494,6 +1567,128 @@ mod tests {
         assert_eq!(assignment.value(), &Expression::Number(100));
     }
 
+    #[test]
+    fn test_parse_collection_assignment() {
+        // $paths = [a $HOME]
+        let tokens = [
+            Token::Identifier("paths".to_string()),
+            Token::Equal,
+            Token::String("[a".to_string()),
+            Token::Word(vec![
+                Token::Identifier("HOME".to_string()),
+                Token::String("]".to_string()),
+            ]),
+        ];
+
+        assert_eq!(
+            parse_collection_assignment(&tokens).unwrap().value(),
+            &Expression::List(vec![
+                Expression::String("a".to_string()),
+                Expression::Identifier("HOME".to_string()),
+            ])
+        );
+
+        // $cfg = {k: v, n:1}
+        let tokens = [
+            Token::Identifier("cfg".to_string()),
+            Token::Equal,
+            Token::LeftBrace,
+            Token::String("k:".to_string()),
+            Token::String("v,".to_string()),
+            Token::String("n:1".to_string()),
+            Token::RightBrace,
+        ];
+
+        assert_eq!(
+            parse_collection_assignment(&tokens).unwrap().value(),
+            &Expression::Map(vec![
+                ("k".to_string(), Expression::String("v".to_string())),
+                ("n".to_string(), Expression::String("1".to_string())),
+            ])
+        );
+
+        assert!(parse_collection_assignment(&tokens[..3]).is_err());
+
+        assert!(parse_collection_assignment(&tokens[..2]).is_err());
+    }
+
+    #[test]
+    fn test_parse_collection_assignment_quoted() {
+        let quoted = |string: &str| Expression::Word(vec![Expression::Quoted(string.to_string())]);
+
+        // $p = ["b c"]
+        let tokens = [Token::Word(vec![
+            Token::String("[".to_string()),
+            Token::Quoted("b c".to_string()),
+            Token::String("]".to_string()),
+        ])];
+
+        assert_eq!(
+            parse_list(&tokens).unwrap(),
+            Expression::List(vec![quoted("b c")])
+        );
+
+        // $p = [a "b"]
+        let tokens = [
+            Token::String("[a".to_string()),
+            Token::Word(vec![
+                Token::Quoted("b".to_string()),
+                Token::String("]".to_string()),
+            ]),
+        ];
+
+        assert_eq!(
+            parse_list(&tokens).unwrap(),
+            Expression::List(vec![Expression::String("a".to_string()), quoted("b")])
+        );
+
+        // $m = {k: "v w", "j": x}
+        let tokens = [
+            Token::String("{k:".to_string()),
+            Token::Word(vec![
+                Token::Quoted("v w".to_string()),
+                Token::String(",".to_string()),
+            ]),
+            Token::Word(vec![
+                Token::Quoted("j".to_string()),
+                Token::String(":".to_string()),
+            ]),
+            Token::String("x}".to_string()),
+        ];
+
+        assert_eq!(
+            parse_map(&tokens).unwrap(),
+            Expression::Map(vec![
+                ("k".to_string(), quoted("v w")),
+                ("j".to_string(), Expression::String("x".to_string())),
+            ])
+        );
+
+        // $m = {k: "v"}
+        let tokens = [
+            Token::String("{k:".to_string()),
+            Token::Word(vec![
+                Token::Quoted("v".to_string()),
+                Token::String("}".to_string()),
+            ]),
+        ];
+
+        assert_eq!(
+            parse_map(&tokens).unwrap(),
+            Expression::Map(vec![("k".to_string(), quoted("v"))])
+        );
+    }
+
+    #[test]
+    fn test_parse_index() {
+        assert_eq!(
+            parse_index(&Token::Index("cfg".to_string(), ".k".to_string())).unwrap(),
+            Expression::Index("cfg".to_string(), ".k".to_string())
+        );
+
+        assert!(parse_index(&Token::Identifier("cfg".to_string())).is_err());
+    }
+
     #[test]
     fn test_parse_redirect_abbreviated() {
         let tokens = [
@@ -504,6 +1699,28 @@ mod tests {
         let redirect = parse_redirect(&tokens).unwrap();
 
         assert_eq!(redirect.operator(), &RedirectOperator::GreaterThan);
+
+        let tokens = [
+            Token::DoubleGreaterThan,
+            Token::String("log.txt".to_string()),
+        ];
+
+        let redirect = parse_redirect(&tokens).unwrap();
+
+        assert_eq!(redirect.operator(), &RedirectOperator::DoubleGreaterThan);
+
+        assert_eq!(redirect.left(), &Expression::FileDescriptor(1));
+
+        let tokens = [
+            Token::LessThanGreaterThan,
+            Token::String("fifo".to_string()),
+        ];
+
+        let redirect = parse_redirect(&tokens).unwrap();
+
+        assert_eq!(redirect.operator(), &RedirectOperator::LessThanGreaterThan);
+
+        assert_eq!(redirect.left(), &Expression::FileDescriptor(0));
     }
 
     #[test]
@@ -519,6 +1736,42 @@ mod tests {
         assert_eq!(redirect.operator(), &RedirectOperator::GreaterThan);
     }
 
+    #[test]
+    fn test_parse_heredoc() {
+        // cat <<EOF @3 <<<$x
+        let tokens = [
+            Token::String("cat".to_string()),
+            Token::HereDocument(vec![
+                Token::Identifier("HOME".to_string()),
+                Token::String("\n".to_string()),
+            ]),
+            Token::FileDescriptor(3),
+            Token::TripleLessThan,
+            Token::Identifier("x".to_string()),
+        ];
+
+        let command = parse_command(&tokens).unwrap();
+
+        assert_eq!(
+            command.redirects(),
+            &vec![
+                Redirect::new(
+                    RedirectOperator::HereDocument,
+                    Expression::FileDescriptor(0),
+                    Expression::Word(vec![
+                        Expression::Identifier("HOME".to_string()),
+                        Expression::String("\n".to_string())
+                    ])
+                ),
+                Redirect::new(
+                    RedirectOperator::HereString,
+                    Expression::FileDescriptor(3),
+                    Expression::Identifier("x".to_string())
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_command() {
         let tokens = [
@@ -558,6 +1811,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_command_assignments() {
+        let tokens = [
+            Token::String("RUST_LOG=debug".to_string()),
+            Token::Word(vec![
+                Token::String("A=".to_string()),
+                Token::Quoted("x y".to_string()),
+            ]),
+            Token::String("cargo".to_string()),
+            Token::String("B=2".to_string()),
+        ];
+
+        let command = parse_command(&tokens).unwrap();
+
+        assert_eq!(command.name(), &Expression::String("cargo".to_string()));
+
+        assert_eq!(
+            command.arguments(),
+            &[Expression::String("B=2".to_string())]
+        );
+
+        assert_eq!(
+            command.assignments(),
+            &[
+                Assignment::new(
+                    Expression::Identifier("RUST_LOG".to_string()),
+                    Expression::String("debug".to_string()),
+                ),
+                Assignment::new(
+                    Expression::Identifier("A".to_string()),
+                    Expression::Word(vec![Expression::Quoted("x y".to_string())]),
+                ),
+            ]
+        );
+
+        // an assignment followed by extra tokens is not a prefix.
+        let tokens = [
+            Token::Identifier("s".to_string()),
+            Token::Equal,
+            Token::String("hello".to_string()),
+            Token::String("world".to_string()),
+        ];
+
+        assert_eq!(
+            parse_statement(&tokens).unwrap_err().to_string(),
+            "syntax error: assignment takes a single value, quote it"
+        );
+
+        assert_eq!(
+            Parser::new("$b = hello world")
+                .parse()
+                .unwrap_err()
+                .to_string(),
+            "syntax error: assignment takes a single value, quote it"
+        );
+
+        // a lone assignment-like word is still a command name.
+        let command = parse_command(&[Token::String("A=1".to_string())]).unwrap();
+
+        assert!(command.assignments().is_empty());
+
+        assert_eq!(command.name(), &Expression::String("A=1".to_string()));
+    }
+
     #[test]
     fn test_parse_pipe() {
         let tokens = [
@@ -576,4 +1893,277 @@ mod tests {
         assert_eq!(command.name(), &Expression::String("cat".to_string()));
         assert_eq!(command.arguments()[0], Expression::String("-b".to_string()));
     }
+
+    #[test]
+    fn test_parse_logical() {
+        let tokens = [
+            Token::String("make".to_string()),
+            Token::DoubleAmpersand,
+            Token::String("./run".to_string()),
+            Token::DoublePipe,
+            Token::String("notify".to_string()),
+        ];
+
+        let logical = parse_logical(&tokens).unwrap();
+
+        assert_eq!(logical.operator(), &LogicalOperator::Or);
+
+        assert_eq!(
+            logical.right(),
+            &Statement::Command(Command::new(
+                Vec::new(),
+                Expression::String("notify".to_string()),
+                Vec::new(),
+                Vec::new(),
+                Expression::Boolean(false),
+            ))
+        );
+
+        match logical.left() {
+            Statement::Logical(left) => assert_eq!(left.operator(), &LogicalOperator::And),
+            _ => panic!("expected a nested logical list"),
+        }
+    }
+
+    #[test]
+    fn test_parse_block() {
+        let tokens = [
+            Token::LeftBrace,
+            Token::Semicolon,
+            Token::String("echo".to_string()),
+            Token::String("a".to_string()),
+            Token::Semicolon,
+            Token::String("echo".to_string()),
+            Token::String("b".to_string()),
+            Token::Semicolon,
+            Token::RightBrace,
+        ];
+
+        let mut sequence = match parse_block(&tokens).unwrap() {
+            Statement::Sequence(sequence) => sequence,
+            _ => panic!("expected a sequence"),
+        };
+
+        assert!(matches!(sequence.pop_front(), Some(Statement::Command(_))));
+
+        assert!(matches!(sequence.pop_front(), Some(Statement::Command(_))));
+
+        assert!(sequence.pop_front().is_none());
+
+        assert!(parse_block(&tokens[..tokens.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_parse_group() {
+        // (cd /tmp; ls) > out.txt &
+        let tokens = [
+            Token::LeftParen,
+            Token::String("cd".to_string()),
+            Token::String("/tmp".to_string()),
+            Token::Semicolon,
+            Token::String("ls".to_string()),
+            Token::RightParen,
+            Token::GreaterThan,
+            Token::String("out.txt".to_string()),
+            Token::Ampersand,
+        ];
+
+        let command = parse_command(&tokens).unwrap();
+
+        assert!(matches!(command.name(), Expression::Subshell(_)));
+
+        assert_eq!(command.redirects().len(), 1);
+
+        assert_eq!(command.is_background(), &Expression::Boolean(true));
+
+        // { echo a; } | cat
+        let tokens = [
+            Token::LeftBrace,
+            Token::String("echo".to_string()),
+            Token::String("a".to_string()),
+            Token::Semicolon,
+            Token::RightBrace,
+            Token::Pipe,
+            Token::String("cat".to_string()),
+        ];
+
+        let mut pipe = parse_pipe(&tokens).unwrap();
+
+        assert!(matches!(
+            pipe.pop_front().unwrap().name(),
+            Expression::Group(_)
+        ));
+
+        assert!(parse_group(&tokens[..4]).is_err());
+
+        // a brace and a parenthesis do not match.
+        assert!(parse_group(&[Token::LeftBrace, Token::RightParen]).is_err());
+
+        assert!(parse_group(&[
+            Token::LeftParen,
+            Token::RightParen,
+            Token::String("a".to_string())
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_if() {
+        // if test -d src { echo a } else if true { echo b } else { echo c }
+        let tokens = [
+            Token::String("if".to_string()),
+            Token::String("test".to_string()),
+            Token::String("-d".to_string()),
+            Token::String("src".to_string()),
+            Token::LeftBrace,
+            Token::String("echo".to_string()),
+            Token::String("a".to_string()),
+            Token::RightBrace,
+            Token::String("else".to_string()),
+            Token::String("if".to_string()),
+            Token::Boolean(true),
+            Token::LeftBrace,
+            Token::String("echo".to_string()),
+            Token::String("b".to_string()),
+            Token::RightBrace,
+            Token::String("else".to_string()),
+            Token::LeftBrace,
+            Token::String("echo".to_string()),
+            Token::String("c".to_string()),
+            Token::RightBrace,
+        ];
+
+        let if_statement = parse_if(&tokens).unwrap();
+
+        match if_statement.condition() {
+            Statement::Command(command) => {
+                assert_eq!(command.name(), &Expression::String("test".to_string()));
+                assert_eq!(command.arguments().len(), 2);
+            }
+            _ => panic!("expected a command"),
+        }
+
+        match if_statement.alternative() {
+            Some(Statement::If(alternative)) => assert!(alternative.alternative().is_some()),
+            _ => panic!("expected an else-if"),
+        }
+    }
+
+    #[test]
+    fn test_parse_if_failure() {
+        // if true { echo a } echo b
+        let tokens = [
+            Token::String("if".to_string()),
+            Token::Boolean(true),
+            Token::LeftBrace,
+            Token::String("echo".to_string()),
+            Token::String("a".to_string()),
+            Token::RightBrace,
+            Token::String("echo".to_string()),
+            Token::String("b".to_string()),
+        ];
+
+        assert!(parse_if(&tokens).is_err());
+
+        assert!(parse_if(&tokens[..5]).is_err());
+    }
+
+    #[test]
+    fn test_parse_while() {
+        // while true { break }
+        let tokens = [
+            Token::String("while".to_string()),
+            Token::Boolean(true),
+            Token::LeftBrace,
+            Token::String("break".to_string()),
+            Token::RightBrace,
+        ];
+
+        let while_statement = parse_while(&tokens).unwrap();
+
+        let mut body = Sequence::new();
+
+        body.push_back(Statement::Break);
+
+        assert_eq!(while_statement.body(), &Statement::Sequence(body));
+
+        assert!(parse_while(&tokens[..4]).is_err());
+    }
+
+    #[test]
+    fn test_parse_for() {
+        // for $x in a *.rs { continue }
+        let tokens = [
+            Token::String("for".to_string()),
+            Token::Identifier("x".to_string()),
+            Token::String("in".to_string()),
+            Token::String("a".to_string()),
+            Token::String("*.rs".to_string()),
+            Token::LeftBrace,
+            Token::String("continue".to_string()),
+            Token::RightBrace,
+        ];
+
+        let for_statement = parse_for(&tokens).unwrap();
+
+        assert_eq!(
+            for_statement.identifier(),
+            &Expression::Identifier("x".to_string())
+        );
+
+        assert_eq!(
+            for_statement.items(),
+            &vec![
+                Expression::String("a".to_string()),
+                Expression::String("*.rs".to_string())
+            ]
+        );
+
+        assert!(parse_for(&tokens[1..]).is_err());
+    }
+
+    #[test]
+    fn test_parse_function() {
+        // fn mkcd { mkdir $1; return 0 }
+        let tokens = [
+            Token::String("fn".to_string()),
+            Token::String("mkcd".to_string()),
+            Token::LeftBrace,
+            Token::String("mkdir".to_string()),
+            Token::Identifier("1".to_string()),
+            Token::Semicolon,
+            Token::String("return".to_string()),
+            Token::Number(0),
+            Token::RightBrace,
+        ];
+
+        let function = parse_function(&tokens).unwrap();
+
+        assert_eq!(function.name(), &Expression::String("mkcd".to_string()));
+
+        let mut body = match function.body() {
+            Statement::Sequence(sequence) => sequence.clone(),
+            _ => panic!("expected a sequence"),
+        };
+
+        assert!(matches!(body.pop_front(), Some(Statement::Command(_))));
+
+        assert_eq!(
+            body.pop_front(),
+            Some(Statement::Return(Some(Expression::Number(0))))
+        );
+
+        assert!(parse_function(&tokens[..2]).is_err());
+    }
+
+    #[test]
+    fn test_parse_logical_failure() {
+        let tokens = [Token::String("make".to_string()), Token::DoubleAmpersand];
+
+        assert!(parse_logical(&tokens).is_err());
+
+        let tokens = [Token::String("make".to_string())];
+
+        assert!(parse_logical(&tokens).is_err());
+    }
 }
@@ -25,10 +25,29 @@ impl Parser {
     fn collect(&mut self) -> Result<()> {
         self.tokens.clear();
 
+        // semicolons inside a block or a subshell belong to it, not to the statement separator.
+        let mut depth = 0usize;
+
         loop {
             let token = self.lexer.next()?;
 
             match token {
+                Token::LeftBrace | Token::LeftParen => {
+                    depth += 1;
+                    self.tokens.push(token);
+                }
+
+                Token::RightBrace | Token::RightParen => {
+                    depth = depth.checked_sub(1).ok_or(Error::NOT_IMPLEMENTED)?;
+                    self.tokens.push(token);
+                }
+
+                Token::Semicolon if depth > 0 => {
+                    self.tokens.push(token);
+                }
+
+                Token::EOF if depth > 0 => Err(Error::NOT_IMPLEMENTED)?,
+
                 Token::Semicolon | Token::EOF => break,
 
                 Token::Ampersand => {
@@ -45,44 +64,17 @@ impl Parser {
         Ok(())
     }
 
-    fn parse_assignment(&mut self) -> Result<Assignment> {
-        let tokens = self
-            .tokens
-            .get(self.index..self.index + 3)
-            .map(|tokens| TryInto::<&[Token; 3]>::try_into(tokens).unwrap())
-            .ok_or(Error::NOT_IMPLEMENTED)?;
-
-        let assignment = lite::parse_assignment(tokens)?;
-
-        self.index += 3;
-
-        Ok(assignment)
-    }
-
-    fn parse_command(&mut self) -> Result<Command> {
-        let tokens = self
-            .tokens
-            .get(self.index..self.length - self.index)
-            .ok_or(Error::NOT_IMPLEMENTED)?;
-
-        let command = lite::parse_command(tokens)?;
-
-        self.index = self.length;
-
-        Ok(command)
-    }
-
-    fn parse_pipe(&mut self) -> Result<Pipe> {
+    fn parse_statement(&mut self) -> Result<Statement> {
         let tokens = self
             .tokens
             .get(self.index..self.length - self.index)
             .ok_or(Error::NOT_IMPLEMENTED)?;
 
-        let pipe = lite::parse_pipe(tokens)?;
+        let statement = lite::parse_statement(tokens)?;
 
         self.index = self.length;
 
-        Ok(pipe)
+        Ok(statement)
     }
 
     pub fn parse(&mut self) -> Result<Statement> {
@@ -116,11 +108,8 @@ impl Parser {
             //     })
             //     .or_else(|_| self.parse_pipe().map(Statement::Pipe))?;
 
-            let statement = self
-                .parse_assignment()
-                .map(Statement::Assignment)
-                .or_else(|_| self.parse_command().map(Statement::Command))
-                .or_else(|_| self.parse_pipe().map(Statement::Pipe))?;
+            // assignments are parsed along with every other statement form.
+            let statement = self.parse_statement()?;
 
             sequence.push_back(statement);
         }
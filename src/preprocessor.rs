@@ -7,6 +7,9 @@ use std::borrow::Cow;
 /// 3. Replaces newlines with semicolons (`;`) to unify statement separation.
 /// 4. Collapses consecutive semicolons into a single one.
 ///
+/// Here-document bodies are kept verbatim, including their newlines, so the lexer
+/// can read them after the line of their `<<` operator.
+///
 /// This function is typically used to prepare input for parsing by
 /// ensuring consistent, linear structure.
 ///
@@ -19,7 +22,192 @@ use std::borrow::Cow;
 pub fn preprocess(source: impl Into<String>) -> String {
     let source = source.into();
 
-    let source = remove_comments(&source);
+    let mut preprocessed = String::with_capacity(source.len());
+
+    for segment in split_heredocs(&source) {
+        match segment {
+            Segment::Code(code) => {
+                let code = normalize(code);
+
+                // the statement after a here-document body starts on a new line.
+                if preprocessed.ends_with('\n') && !code.is_empty() && !code.starts_with(';') {
+                    preprocessed.push(';');
+                }
+
+                preprocessed.push_str(&code);
+            }
+
+            Segment::Body(body, _) => {
+                preprocessed.push_str(body);
+
+                if !body.ends_with('\n') {
+                    preprocessed.push('\n');
+                }
+            }
+        }
+    }
+
+    preprocessed
+}
+
+/// Returns `true` if the source ends inside a here-document body, i.e. a delimiter line is missing.
+///
+/// This is used to keep reading lines for a here-document typed at the prompt.
+pub fn is_heredoc_unterminated(source: &str) -> bool {
+    split_heredocs(source)
+        .iter()
+        .any(|segment| matches!(segment, Segment::Body(_, false)))
+}
+
+/// A part of the source: shell code, or a here-document body and whether its delimiter line was found.
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    Code(&'a str),
+    Body(&'a str, bool),
+}
+
+/// Splits the source into shell code and here-document bodies.
+///
+/// A body starts with the newline ending the line of its `<<DELIM` (or `<<-DELIM`) operator
+/// and runs through the delimiter line. The bodies of several here-documents on one line
+/// follow each other in order.
+fn split_heredocs(source: &str) -> Vec<Segment<'_>> {
+    let chars = source.char_indices().collect::<Vec<_>>();
+
+    let mut segments = Vec::new();
+
+    let mut delimiters: Vec<(String, bool)> = Vec::new();
+
+    let mut start = 0;
+    let mut index = 0;
+
+    let mut is_comment = false;
+    let mut quote: Option<char> = None;
+    let mut is_escaped = false;
+    let mut previous: Option<char> = None;
+
+    while let Some(&(offset, c)) = chars.get(index) {
+        index += 1;
+
+        if c == '\n' && quote.is_none() && !is_escaped && !delimiters.is_empty() {
+            is_comment = false;
+
+            segments.push(Segment::Code(&source[start..offset]));
+
+            let mut line_start = offset + 1;
+
+            let mut is_terminated = true;
+
+            for (delimiter, is_tab_stripped) in delimiters.drain(..) {
+                is_terminated = false;
+
+                while line_start < source.len() {
+                    let line_end = source[line_start..]
+                        .find('\n')
+                        .map_or(source.len(), |n| line_start + n);
+
+                    let line = source[line_start..line_end].trim_end_matches('\r');
+
+                    let line = match is_tab_stripped {
+                        true => line.trim_start_matches('\t'),
+                        false => line,
+                    };
+
+                    line_start = (line_end + 1).min(source.len());
+
+                    if line == delimiter {
+                        is_terminated = true;
+                        break;
+                    }
+                }
+            }
+
+            segments.push(Segment::Body(&source[offset..line_start], is_terminated));
+
+            start = line_start;
+
+            index = chars.partition_point(|(offset, _)| *offset < start);
+        } else if is_comment {
+            is_comment = c != '\n';
+        } else if is_escaped {
+            is_escaped = false;
+        } else if c == '\\' && quote != Some('\'') {
+            is_escaped = true;
+        } else if quote == Some(c) {
+            quote = None;
+        } else if quote.is_some() {
+            // quoted text has no operators.
+        } else if c == '\'' || c == '"' {
+            quote = Some(c);
+        } else if c == '#' && previous.is_none_or(|p| p.is_whitespace() || p == ';') {
+            is_comment = true;
+        } else if c == '<' && chars.get(index).is_some_and(|(_, c)| *c == '<') {
+            index += 1;
+
+            match chars.get(index) {
+                // a here-string (`<<<`).
+                Some((_, '<')) => index += 1,
+
+                Some((_, '-')) => {
+                    index += 1;
+                    delimiters.push((read_delimiter(&chars, &mut index), true));
+                }
+
+                _ => delimiters.push((read_delimiter(&chars, &mut index), false)),
+            }
+        }
+
+        previous = Some(c);
+    }
+
+    segments.push(Segment::Code(&source[start..]));
+
+    // the operator's line is the last one, so its body is still to come.
+    if !delimiters.is_empty() {
+        segments.push(Segment::Body("", false));
+    }
+
+    segments
+}
+
+/// Reads the delimiter word of a here-document operator, removing its quotes and backslashes.
+fn read_delimiter(chars: &[(usize, char)], index: &mut usize) -> String {
+    let mut delimiter = String::new();
+
+    let mut quote: Option<char> = None;
+
+    while let Some(&(_, c)) = chars.get(*index) {
+        match c {
+            c if quote == Some(c) => quote = None,
+
+            c if quote.is_some() => delimiter.push(c),
+
+            ' ' | '\t' if delimiter.is_empty() => {}
+
+            c if c.is_whitespace() || matches!(c, ';' | '&' | '|' | '<' | '>') => break,
+
+            '\'' | '"' => quote = Some(c),
+
+            '\\' => {
+                *index += 1;
+
+                if let Some(&(_, c)) = chars.get(*index) {
+                    delimiter.push(c);
+                }
+            }
+
+            c => delimiter.push(c),
+        }
+
+        *index += 1;
+    }
+
+    delimiter
+}
+
+/// Applies the normalization steps of `preprocess` to shell code.
+fn normalize(source: &str) -> String {
+    let source = remove_comments(source);
 
     let source = remove_empty_line(&source);
 
@@ -44,13 +232,14 @@ pub fn preprocess(source: impl Into<String>) -> String {
     }
 
     cleaned
-    
 }
 
 /// Removes comments (`# ...`) from the input source, ignoring those inside quotes.
 ///
-/// A comment starts with `#` and continues until a newline, semicolon, or carriage return,
-/// unless it appears within a quoted string (single or double quotes).
+/// A comment starts with `#` at the beginning of a word and continues until a newline,
+/// semicolon, or carriage return, unless it appears within a quoted string
+/// (single or double quotes) or is escaped (`\#`). A `#` inside a word, such as
+/// in `${PATH#*:}`, does not start a comment.
 ///
 /// # Arguments
 /// - `source`: The input string to clean.
@@ -62,24 +251,47 @@ fn remove_comments(source: &str) -> Cow<'_, str> {
     let mut result = String::with_capacity(source.len());
 
     let mut is_comment = false;
-    let mut is_quote = false;
+    let mut quote: Option<char> = None;
+    let mut is_escaped = false;
+    let mut previous: Option<char> = None;
 
     source.chars().for_each(|c| {
-        if c == '\'' || c == '"' {
-            is_quote = !is_quote;
+        if is_comment {
+            // inside a comment, quotes and backslashes have no meaning.
+        } else if is_escaped {
+            is_escaped = false;
+        } else if c == '\\' && quote != Some('\'') {
+            is_escaped = true;
+        } else if quote == Some(c) {
+            quote = None;
+        } else if quote.is_none() && (c == '\'' || c == '"') {
+            quote = Some(c);
+        } else if c == '#'
+            && quote.is_none()
+            && previous.is_none_or(|p| p.is_whitespace() || p == ';')
+        {
+            is_comment = true;
         }
 
-        if c == '#' && is_quote == false {
-            is_comment = true;
+        // the line break ending a comment after a statement still separates it from the next one.
+        if is_comment && c == '\n' {
+            is_comment = false;
+
+            if result.is_empty() || result.ends_with('\n') {
+                previous = Some(c);
+                return;
+            }
         }
 
         if is_comment == false {
             result.push(c);
         }
 
-        if c == '\r' || c == '\n' || c == ';' {
+        if c == '\r' || c == ';' {
             is_comment = false;
         }
+
+        previous = Some(c);
     });
 
     if result.len() == source.len() {
@@ -160,6 +372,20 @@ mod tests {
         assert_eq!(remove_comments("# full line comment\nhello"), "hello");
 
         assert_eq!(remove_comments("hello\n# comment\nworld"), "hello\nworld");
+
+        assert_eq!(remove_comments("a # comment\nb"), "a \nb");
+
+        assert_eq!(remove_comments("echo \\# not # comment"), "echo \\# not ");
+
+        assert_eq!(
+            remove_comments("echo ${p##*/} a#b;# comment"),
+            "echo ${p##*/} a#b;"
+        );
+
+        assert_eq!(
+            remove_comments("echo \"it's \\\" # quoted\" # comment"),
+            "echo \"it's \\\" # quoted\" "
+        );
     }
 
     #[test]
@@ -183,6 +409,29 @@ mod tests {
         assert_eq!(remove_empty_line("line1\n\n\nline2"), "line1\nline2");
     }
 
+    #[test]
+    fn test_preprocess_heredoc() {
+        assert_eq!(
+            preprocess("cat <<EOF | sort\n# kept\n\n  b;a\nEOF\necho done\n"),
+            "cat <<EOF | sort\n# kept\n\n  b;a\nEOF\n;echo done"
+        );
+
+        assert_eq!(
+            preprocess("cat <<-'A' <<B\n\ta\n\tA\nb\nB"),
+            "cat <<-'A' <<B\n\ta\n\tA\nb\nB\n"
+        );
+
+        assert_eq!(preprocess("cat <<< x # <<EOF\necho"), "cat <<< x ;echo");
+
+        assert!(is_heredoc_unterminated("cat <<EOF\na"));
+
+        assert!(is_heredoc_unterminated("cat <<EOF"));
+
+        assert!(!is_heredoc_unterminated("cat <<EOF\na\nEOF"));
+
+        assert!(!is_heredoc_unterminated("echo '<<EOF'"));
+    }
+
 }
 
 mod benches {
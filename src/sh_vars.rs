@@ -1,16 +1,74 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     env::{self, Vars},
+    fmt,
 };
 
 use crate::{error::*, result::*};
 
-/// A wrapper around a `HashMap<String, String>` for managing shell variables.
+/// The value of a shell variable: a string, a list (`[a b c]`), or a map (`{k: v}`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    List(Vec<String>),
+    Map(BTreeMap<String, String>),
+}
+
+impl Value {
+    /// Flattens the value into a single string for the environment of a child process.
+    ///
+    /// - A list is joined with `:`, as in `PATH` (e.g., `/bin:/usr/bin`).
+    /// - A map is written as `key=value` pairs sorted by key and joined with `,` (e.g., `a=1,b=2`).
+    ///
+    /// # Returns
+    /// The flattened string.
+    pub fn flatten(&self) -> String {
+        match self {
+            Self::String(string) => string.to_string(),
+            Self::List(list) => list.join(":"),
+            Self::Map(map) => map
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// Formats the value as it appears inside a word (e.g., `"paths: $paths"`):
+/// the items of a list, or the `key=value` pairs of a map, separated by spaces.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(string) => write!(f, "{string}"),
+            Self::List(list) => write!(f, "{}", list.join(" ")),
+            Self::Map(map) => write!(
+                f,
+                "{}",
+                map.iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        }
+    }
+}
+
+/// A wrapper around a `HashMap<String, Value>` for managing shell variables.
 ///
 /// `ShVars` provides a simple interface for inserting, removing, and querying
 /// key-value pairs that represent shell environment or user-defined variables.
+/// Values are strings, or lists and maps assigned by the user.
+///
+/// Only exported variables are passed to child processes; variables inherited
+/// from the host process are exported, plain assignments stay shell-local.
+/// Variables declared `local` are restored when their scope is popped.
 #[derive(Debug, Clone)]
-pub struct ShVars(HashMap<String, String>);
+pub struct ShVars {
+    vars: HashMap<String, Value>,
+    exported: HashSet<String>,
+    scopes: Vec<HashMap<String, (Option<Value>, bool)>>,
+}
 
 impl ShVars {
     /// Creates a new, empty `ShVars` instance.
@@ -18,22 +76,30 @@ impl ShVars {
     /// # Returns
     /// A new `ShVars` with no entries.
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            vars: HashMap::new(),
+            exported: HashSet::new(),
+            scopes: Vec::new(),
+        }
     }
 
-    /// Extends the internal map with environment variables from the host process.
+    /// Extends the internal map with environment variables from the host process,
+    /// which are marked as exported.
     ///
     /// # Arguments
     /// - `env_vars`: An iterator over environment variables, typically `std::env::vars()`.
     pub fn inherit(&mut self, env_vars: Vars) {
-        self.0.extend(env_vars);
+        for (key, value) in env_vars {
+            self.exported.insert(key.clone());
+            self.vars.insert(key, Value::String(value));
+        }
     }
 
     /// Inserts a key-value pair into the variable map.
     ///
     /// - If the `key` is an empty string, this function returns an error.
     /// - If the `value` is an empty string, it is automatically replaced with the literal string `"null"`.
-    /// - If the key already exists, its value is overwritten.
+    /// - If the key already exists, its value is overwritten and it stays exported if it was.
     ///
     /// # Arguments
     /// - `key`: The variable name to insert. Must be non-empty.
@@ -55,21 +121,59 @@ impl ShVars {
             Err(Error::NOT_IMPLEMENTED)?
         }
 
-        self.0.insert(key, value);
+        self.vars.insert(key, Value::String(value));
 
         Ok(())
     }
 
-    /// Retrieves the value associated with the given key.
+    /// Inserts a list or map (or string) value into the variable map.
+    ///
+    /// Unlike `insert`, the value is stored as is.
+    ///
+    /// # Arguments
+    /// - `key`: The variable name to insert. Must be non-empty.
+    /// - `value`: The value to associate with the key.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the insertion succeeds.
+    /// - `Err(Error::NOT_IMPLEMENTED)` if the key is empty.
+    pub fn insert_value(&mut self, key: impl Into<String>, value: Value) -> Result<()> {
+        let key = key.into();
+
+        if key.is_empty() {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        self.vars.insert(key, value);
+
+        Ok(())
+    }
+
+    /// Retrieves the string value associated with the given key.
     ///
     /// # Arguments
     /// - `key`: The variable name to look up.
     ///
     /// # Returns
-    /// - `Some(&String)` if the key exists.
-    /// - `None` otherwise.
+    /// - `Some(&String)` if the key exists and holds a string.
+    /// - `None` otherwise (use `get_value` for lists and maps).
     pub fn get(&self, key: impl Into<String>) -> Option<&String> {
-        self.0.get(&key.into())
+        match self.vars.get(&key.into()) {
+            Some(Value::String(string)) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Retrieves the value associated with the given key, which may be a list or a map.
+    ///
+    /// # Arguments
+    /// - `key`: The variable name to look up.
+    ///
+    /// # Returns
+    /// - `Some(&Value)` if the key exists.
+    /// - `None` otherwise.
+    pub fn get_value(&self, key: impl Into<String>) -> Option<&Value> {
+        self.vars.get(&key.into())
     }
 
     /// Returns a list of all variable keys.
@@ -77,35 +181,147 @@ impl ShVars {
     /// # Returns
     /// A `Vec` of references to the keys in the map.
     pub fn keys(&self) -> Vec<&String> {
-        self.0.keys().collect()
+        self.vars.keys().collect()
     }
 
     /// Returns a list of all variable values.
     ///
     /// # Returns
     /// A `Vec` of references to the values in the map.
-    pub fn values(&self) -> Vec<&String> {
-        self.0.values().collect()
+    pub fn values(&self) -> Vec<&Value> {
+        self.vars.values().collect()
     }
 
-    /// Returns all key-value pairs as references.
+    /// Returns all key-value pairs with a string value as references.
     ///
     /// # Returns
     /// A `HashMap` of references to keys and values.
     pub fn entries(&self) -> HashMap<&String, &String> {
-        self.0.iter().collect()
+        self.vars
+            .iter()
+            .filter_map(|(key, value)| match value {
+                Value::String(string) => Some((key, string)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the exported variables as the environment of a child process,
+    /// with lists and maps flattened by `Value::flatten`.
+    ///
+    /// # Returns
+    /// A `HashMap` of keys and flattened values.
+    pub fn environment(&self) -> HashMap<&String, String> {
+        self.vars
+            .iter()
+            .filter(|(key, _)| self.exported.contains(*key))
+            .map(|(key, value)| (key, value.flatten()))
+            .collect()
+    }
+
+    /// Marks a variable as exported, so it is passed to child processes.
+    ///
+    /// The variable does not need to be set yet; it is exported once it is assigned.
+    ///
+    /// # Arguments
+    /// - `key`: The variable name to export. Must be non-empty.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the variable was marked.
+    /// - `Err(Error::NOT_IMPLEMENTED)` if the key is empty.
+    pub fn export(&mut self, key: impl Into<String>) -> Result<()> {
+        let key = key.into();
+
+        if key.is_empty() {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        self.exported.insert(key);
+
+        Ok(())
+    }
+
+    /// Checks whether a variable is marked as exported.
+    ///
+    /// # Arguments
+    /// - `key`: The variable name to check.
+    ///
+    /// # Returns
+    /// `true` if the variable is exported, `false` otherwise.
+    pub fn is_exported(&self, key: impl Into<String>) -> bool {
+        self.exported.contains(&key.into())
+    }
+
+    /// Opens a new scope for `local` variables (e.g., when a function or block starts).
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Closes the innermost scope, restoring the values and export flags
+    /// that the variables declared `local` in it had before.
+    pub fn pop_scope(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+
+        for (key, (value, exported)) in scope {
+            match value {
+                Some(value) => self.vars.insert(key.clone(), value),
+                None => self.vars.remove(&key),
+            };
+
+            if exported {
+                self.exported.insert(key);
+            } else {
+                self.exported.remove(&key);
+            }
+        }
+    }
+
+    /// Declares a variable local to the innermost scope: its current value and
+    /// export flag are saved to be restored by `pop_scope`, and it is unset.
+    ///
+    /// # Arguments
+    /// - `key`: The variable name to declare. Must be non-empty.
+    ///
+    /// # Returns
+    /// - `Ok(())` if the variable was declared.
+    /// - `Err(Error::NOT_IMPLEMENTED)` if the key is empty or no scope is open.
+    pub fn declare_local(&mut self, key: impl Into<String>) -> Result<()> {
+        let key = key.into();
+
+        if key.is_empty() {
+            Err(Error::NOT_IMPLEMENTED)?
+        }
+
+        let scope = self.scopes.last_mut().ok_or(Error::NOT_IMPLEMENTED)?;
+
+        if !scope.contains_key(&key) {
+            let saved = (self.vars.get(&key).cloned(), self.exported.contains(&key));
+
+            scope.insert(key.clone(), saved);
+        }
+
+        self.vars.remove(&key);
+        self.exported.remove(&key);
+
+        Ok(())
     }
 
-    /// Removes a key-value pair from the map.
+    /// Removes a key-value pair from the map, and its export flag.
     ///
     /// # Arguments
     /// - `key`: The variable name to remove.
     ///
     /// # Returns
-    /// - `Some(String)` if the key existed and was removed.
+    /// - `Some(String)` if the key existed and was removed (lists and maps are formatted by `Display`).
     /// - `None` otherwise.
     pub fn remove(&mut self, key: impl Into<String>) -> Option<String> {
-        self.0.remove(&key.into())
+        let key = key.into();
+
+        self.exported.remove(&key);
+
+        self.vars.remove(&key).map(|value| value.to_string())
     }
 
     /// Checks whether a given key exists in the variable map.
@@ -116,7 +332,7 @@ impl ShVars {
     /// # Returns
     /// `true` if the key exists, `false` otherwise.
     pub fn exists(&mut self, key: impl Into<String>) -> bool {
-        self.0.contains_key(&key.into())
+        self.vars.contains_key(&key.into())
     }
 
     /// Returns the number of key-value pairs stored in the map.
@@ -124,13 +340,17 @@ impl ShVars {
     /// # Returns
     /// The total number of variables.
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.vars.len()
     }
 }
 
 impl From<env::Vars> for ShVars {
     fn from(vars: env::Vars) -> Self {
-        Self(vars.collect())
+        let mut sh_vars = Self::new();
+
+        sh_vars.inherit(vars);
+
+        sh_vars
     }
 }
 
@@ -192,6 +412,44 @@ mod tests {
         assert_eq!(values.len(), 100);
     }
 
+    #[test]
+    fn test_shvars_lists_and_maps() {
+        let mut shvars = ShVars::new();
+
+        let list = Value::List(vec!["/bin".to_string(), "/usr/bin".to_string()]);
+
+        let map = Value::Map(BTreeMap::from([
+            ("b".to_string(), "2".to_string()),
+            ("a".to_string(), "1".to_string()),
+        ]));
+
+        assert!(shvars.insert_value("paths", list.clone()).is_ok());
+
+        assert!(shvars.insert_value("cfg", map).is_ok());
+
+        assert!(shvars.insert("name", "fsh").is_ok());
+
+        assert!(shvars.export("paths").is_ok());
+
+        assert!(shvars.export("cfg").is_ok());
+
+        assert_eq!(shvars.get("paths"), None);
+
+        assert_eq!(shvars.get_value("paths"), Some(&list));
+
+        assert_eq!(shvars.entries().len(), 1);
+
+        let environment = shvars.environment();
+
+        assert_eq!(environment.get(&"paths".to_string()), Some(&"/bin:/usr/bin".to_string()));
+
+        assert_eq!(environment.get(&"cfg".to_string()), Some(&"a=1,b=2".to_string()));
+
+        assert_eq!(environment.get(&"name".to_string()), None);
+
+        assert_eq!(shvars.remove("paths"), Some("/bin /usr/bin".to_string()));
+    }
+
     #[test]
     fn test_shvars_entries() {
         let mut shvars = ShVars::new();
@@ -209,4 +467,60 @@ mod tests {
             Some(&"value1".to_string()).as_ref()
         );
     }
+
+    #[test]
+    fn test_shvars_export() {
+        let mut shvars = ShVars::new();
+
+        assert!(shvars.insert("local", "1").is_ok());
+
+        assert!(shvars.export("shared").is_ok());
+
+        assert!(shvars.insert("shared", "2").is_ok());
+
+        assert!(shvars.is_exported("shared"));
+
+        assert!(!shvars.is_exported("local"));
+
+        assert_eq!(shvars.environment().len(), 1);
+
+        assert_eq!(shvars.remove("shared"), Some("2".to_string()));
+
+        assert!(!shvars.is_exported("shared"));
+
+        assert!(shvars.export("").is_err());
+    }
+
+    #[test]
+    fn test_shvars_scopes() {
+        let mut shvars = ShVars::new();
+
+        assert!(shvars.declare_local("x").is_err());
+
+        assert!(shvars.insert("x", "outer").is_ok());
+
+        assert!(shvars.export("x").is_ok());
+
+        shvars.push_scope();
+
+        assert!(shvars.declare_local("x").is_ok());
+
+        assert!(!shvars.exists("x"));
+
+        assert!(shvars.insert("x", "inner").is_ok());
+
+        assert!(shvars.declare_local("y").is_ok());
+
+        assert!(shvars.insert("y", "inner").is_ok());
+
+        assert!(!shvars.is_exported("x"));
+
+        shvars.pop_scope();
+
+        assert_eq!(shvars.get("x"), Some(&"outer".to_string()));
+
+        assert!(shvars.is_exported("x"));
+
+        assert!(!shvars.exists("y"));
+    }
 }
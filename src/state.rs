@@ -1,13 +1,37 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
     io::{PipeReader, PipeWriter},
+    os::unix::io::OwnedFd,
     path::{Path, PathBuf},
-    process,
+    process, thread,
 };
 
+use crate::{ast::statement::Statement, manifest};
+
+/// Represents a pending change of control flow, requested by `break`, `continue`, `return`
+/// or `exit` (in a subshell).
+///
+/// The request is set by the statement and consumed by the innermost enclosing loop
+/// (or function, for `return`, or subshell, for `exit`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlow {
+    /// Leave the innermost loop.
+    Break,
+
+    /// Skip to the next iteration of the innermost loop.
+    Continue,
+
+    /// Leave the innermost function.
+    Return,
+
+    /// Leave the innermost subshell.
+    Exit,
+}
+
 /// Represents the global state of the shell during execution.
 ///
 /// This includes child processes, active pipe handles,
-/// and the current working directory context.
+/// the current working directory context and the last exit status.
 pub struct State {
     /// A list of spawned child processes and a flag indicating foreground/background execution.
     processes: Vec<(process::Child, bool)>,
@@ -15,8 +39,52 @@ pub struct State {
     /// A tuple representing the read and write ends of the active pipe, if any.
     pipe: (Option<PipeReader>, Option<PipeWriter>),
 
+    /// The file descriptors above stdout redirected for every command of the enclosing
+    /// groups (e.g., `@2` of `{ ... } @2 > err.txt`), by number.
+    fds: BTreeMap<i32, OwnedFd>,
+
     /// The current working directory.
     current_dir: PathBuf,
+
+    /// The previous working directory, before the last `cd`.
+    previous_dir: Option<PathBuf>,
+
+    /// The exit status of the most recently executed command.
+    exit_status: i32,
+
+    /// The pending control flow request, if any.
+    control_flow: Option<ControlFlow>,
+
+    /// The number of loops currently being executed.
+    loop_depth: usize,
+
+    /// The user-defined functions, by name.
+    functions: HashMap<String, Statement>,
+
+    /// The positional arguments (`$1`, `$2`, ...) of the function being executed,
+    /// or of the script.
+    arguments: Vec<String>,
+
+    /// The name of the script being executed, or of the shell (`$0`).
+    script_name: String,
+
+    /// The process ID of the most recent background command (`$!`), if any.
+    last_background_pid: Option<u32>,
+
+    /// The number of functions currently being executed.
+    function_depth: usize,
+
+    /// The names of the shell options that are set (e.g., `noclobber`).
+    options: BTreeSet<String>,
+
+    /// The number of subshells currently being executed.
+    subshell_depth: usize,
+
+    /// The running process substitutions (e.g., `<(sort a.txt)`), each on its own thread.
+    process_substitutions: Vec<thread::JoinHandle<i32>>,
+
+    /// The running background pipes and groups (e.g., `sleep 2 | cat &`), each on its own thread.
+    background_jobs: Vec<thread::JoinHandle<i32>>,
 }
 
 impl State {
@@ -25,7 +93,21 @@ impl State {
         Self {
             processes: Vec::new(),
             pipe: (None, None),
+            fds: BTreeMap::new(),
             current_dir: PathBuf::new(),
+            previous_dir: None,
+            exit_status: 0,
+            control_flow: None,
+            loop_depth: 0,
+            functions: HashMap::new(),
+            arguments: Vec::new(),
+            script_name: String::from(manifest::MANIFEST_FSH_NAME),
+            last_background_pid: None,
+            function_depth: 0,
+            options: BTreeSet::new(),
+            subshell_depth: 0,
+            process_substitutions: Vec::new(),
+            background_jobs: Vec::new(),
         }
     }
 
@@ -53,6 +135,16 @@ impl State {
         &mut self.pipe
     }
 
+    /// Returns an immutable reference to the file descriptors redirected for the enclosing groups.
+    pub fn fds(&self) -> &BTreeMap<i32, OwnedFd> {
+        &self.fds
+    }
+
+    /// Returns a mutable reference to the file descriptors redirected for the enclosing groups.
+    pub fn fds_mut(&mut self) -> &mut BTreeMap<i32, OwnedFd> {
+        &mut self.fds
+    }
+
     /// Returns an immutable reference to the current working directory.
     pub fn current_dir(&self) -> &Path {
         &self.current_dir
@@ -62,6 +154,142 @@ impl State {
     pub fn current_dir_mut(&mut self) -> &mut PathBuf {
         &mut self.current_dir
     }
+
+    /// Returns the previous working directory, before the last `cd`, if any.
+    pub fn previous_dir(&self) -> Option<&Path> {
+        self.previous_dir.as_deref()
+    }
+
+    /// Returns a mutable reference to the previous working directory.
+    pub fn previous_dir_mut(&mut self) -> &mut Option<PathBuf> {
+        &mut self.previous_dir
+    }
+
+    /// Returns the exit status of the most recently executed command.
+    ///
+    /// `0` means success; any other value means failure.
+    pub fn exit_status(&self) -> i32 {
+        self.exit_status
+    }
+
+    /// Returns a mutable reference to the exit status of the most recently executed command.
+    pub fn exit_status_mut(&mut self) -> &mut i32 {
+        &mut self.exit_status
+    }
+
+    /// Returns the pending control flow request, if any.
+    ///
+    /// While a request is pending, the remaining statements of the enclosing sequences are skipped.
+    pub fn control_flow(&self) -> Option<ControlFlow> {
+        self.control_flow
+    }
+
+    /// Returns a mutable reference to the pending control flow request.
+    pub fn control_flow_mut(&mut self) -> &mut Option<ControlFlow> {
+        &mut self.control_flow
+    }
+
+    /// Returns the number of loops currently being executed.
+    pub fn loop_depth(&self) -> usize {
+        self.loop_depth
+    }
+
+    /// Returns a mutable reference to the number of loops currently being executed.
+    pub fn loop_depth_mut(&mut self) -> &mut usize {
+        &mut self.loop_depth
+    }
+
+    /// Returns an immutable reference to the user-defined functions.
+    pub fn functions(&self) -> &HashMap<String, Statement> {
+        &self.functions
+    }
+
+    /// Returns a mutable reference to the user-defined functions.
+    pub fn functions_mut(&mut self) -> &mut HashMap<String, Statement> {
+        &mut self.functions
+    }
+
+    /// Returns an immutable reference to the positional arguments.
+    ///
+    /// `$1` is the first element.
+    pub fn arguments(&self) -> &Vec<String> {
+        &self.arguments
+    }
+
+    /// Returns a mutable reference to the positional arguments.
+    pub fn arguments_mut(&mut self) -> &mut Vec<String> {
+        &mut self.arguments
+    }
+
+    /// Returns the name of the script being executed, or of the shell.
+    pub fn script_name(&self) -> &str {
+        &self.script_name
+    }
+
+    /// Returns a mutable reference to the name of the script being executed.
+    pub fn script_name_mut(&mut self) -> &mut String {
+        &mut self.script_name
+    }
+
+    /// Returns the process ID of the most recent background command, if any.
+    pub fn last_background_pid(&self) -> Option<u32> {
+        self.last_background_pid
+    }
+
+    /// Returns a mutable reference to the process ID of the most recent background command.
+    pub fn last_background_pid_mut(&mut self) -> &mut Option<u32> {
+        &mut self.last_background_pid
+    }
+
+    /// Returns the number of functions currently being executed.
+    pub fn function_depth(&self) -> usize {
+        self.function_depth
+    }
+
+    /// Returns a mutable reference to the number of functions currently being executed.
+    pub fn function_depth_mut(&mut self) -> &mut usize {
+        &mut self.function_depth
+    }
+
+    /// Returns an immutable reference to the names of the shell options that are set.
+    pub fn options(&self) -> &BTreeSet<String> {
+        &self.options
+    }
+
+    /// Returns a mutable reference to the names of the shell options that are set.
+    pub fn options_mut(&mut self) -> &mut BTreeSet<String> {
+        &mut self.options
+    }
+
+    /// Returns the number of subshells currently being executed.
+    pub fn subshell_depth(&self) -> usize {
+        self.subshell_depth
+    }
+
+    /// Returns a mutable reference to the number of subshells currently being executed.
+    pub fn subshell_depth_mut(&mut self) -> &mut usize {
+        &mut self.subshell_depth
+    }
+
+    /// Returns an immutable reference to the running process substitutions.
+    pub fn process_substitutions(&self) -> &Vec<thread::JoinHandle<i32>> {
+        &self.process_substitutions
+    }
+
+    /// Returns a mutable reference to the running process substitutions.
+    pub fn process_substitutions_mut(&mut self) -> &mut Vec<thread::JoinHandle<i32>> {
+        &mut self.process_substitutions
+    }
+
+    /// Returns an immutable reference to the running background pipes and groups.
+    pub fn background_jobs(&self) -> &Vec<thread::JoinHandle<i32>> {
+        &self.background_jobs
+    }
+
+    /// Returns a mutable reference to the running background pipes and groups.
+    pub fn background_jobs_mut(&mut self) -> &mut Vec<thread::JoinHandle<i32>> {
+        &mut self.background_jobs
+    }
 }
 
 impl From<PathBuf> for State {
@@ -13,6 +13,24 @@ pub enum Token {
     /// A pipe (`|`), used to express sequential data flow between elements.
     Pipe,
 
+    /// A double ampersand (`&&`), runs the right side only if the left side succeeds.
+    DoubleAmpersand,
+
+    /// A double pipe (`||`), runs the right side only if the left side fails.
+    DoublePipe,
+
+    /// A left brace (`{`) standing alone as a word, used to open a block.
+    LeftBrace,
+
+    /// A right brace (`}`) standing alone as a word, used to close a block.
+    RightBrace,
+
+    /// A left parenthesis (`(`) at the start of a word, used to open a subshell.
+    LeftParen,
+
+    /// A right parenthesis (`)`) not matching a `(` within the same word, used to close a subshell.
+    RightParen,
+
     /// A single equals sign (`=`), used for assignment operations.
     ///
     /// Note: Comparison operations (e.g., `==`) are handled separately.
@@ -26,15 +44,63 @@ pub enum Token {
     /// symbol or as a comparison operator depending on syntactic context.
     GreaterThan,
 
+    /// A double greater-than sign (`>>`), an output redirection that appends to the file.
+    DoubleGreaterThan,
+
+    /// A greater-than sign followed by a pipe (`>|`), an output redirection that
+    /// overwrites the file even if the `noclobber` option is set.
+    GreaterThanPipe,
+
+    /// A less-than sign followed by a greater-than sign (`<>`), a redirection that
+    /// opens the file for reading and writing.
+    LessThanGreaterThan,
+
+    /// A triple less-than sign (`<<<`), introducing a here-string.
+    TripleLessThan,
+
+    /// A here-document, e.g., `<<EOF` followed by its body lines, holding the parts of the body.
+    /// The body is a single `String` if the delimiter was quoted, otherwise it may also contain
+    /// expansion parts as in a `Word`.
+    HereDocument(Vec<Token>),
+
     /// Represents an explicit null token.
     Null,
 
     /// A string literal, e.g., `text` or `"Hello, World!"`, `'0123'`.
     String(String),
 
+    /// Quoted or escaped literal text within a word, e.g., `"*.rs"` or `\*`,
+    /// which is not subject to brace, tilde or glob expansion.
+    Quoted(String),
+
     /// A shell variable identifier, e.g., `$USER` or `$PATH`.
     Identifier(String),
 
+    /// An identifier with an accessor, e.g., `$paths[0]` or `$cfg.key`,
+    /// holding the identifier and the accessor as written (`[0]` or `.key`).
+    Index(String, String),
+
+    /// A command substitution, e.g., `$(git branch --show-current)`, holding the inner source.
+    Substitution(String),
+
+    /// An input process substitution, e.g., `<(sort a.txt)`, holding the inner source.
+    InputSubstitution(String),
+
+    /// An output process substitution, e.g., `>(gzip > out.gz)`, holding the inner source.
+    OutputSubstitution(String),
+
+    /// An arithmetic substitution, e.g., `$(($n + 1))`, holding the inner source.
+    Arithmetic(String),
+
+    /// A braced parameter expansion, e.g., `${HOME:-/root}`, holding the name
+    /// (with a leading `#` for the length form `${#HOME}`), the operator, and the operand parts.
+    Parameter(String, String, Vec<Token>),
+
+    /// A word made of adjacent parts that form a single argument, e.g., `"$HOME"/bin` or `foo$BAR`.
+    /// Each part is a `String`, `Quoted`, `Identifier`, `Parameter` or `Substitution`.
+    /// A quoted word such as `"a b"` is a word with a single `Quoted` part.
+    Word(Vec<Token>),
+
     /// A boolean literal (`true` or `false`).
     Boolean(bool),
 
@@ -1 +1,3 @@
-pub mod path;
\ No newline at end of file
+pub mod brace;
+pub mod glob;
+pub mod path;
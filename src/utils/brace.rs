@@ -0,0 +1,274 @@
+/// A piece of a word for brace expansion: a character that may be part of the brace syntax,
+/// or an opaque piece (e.g., a variable expansion) that is copied as it is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece<T> {
+    Char(char),
+    Opaque(T),
+}
+
+/// Expands the braces of a word into the words they stand for, in order.
+///
+/// - Alternatives: `file.{rs,toml}` becomes `file.rs file.toml`. Alternatives may be empty
+///   or contain nested braces, e.g. `a{,b{1,2}}` becomes `a ab1 ab2`.
+/// - Numeric ranges: `{1..3}` becomes `1 2 3`, and `{3..1}` counts down. An endpoint with
+///   a leading zero pads every number to the same width, e.g. `{08..10}` becomes `08 09 10`.
+/// - Character ranges of letters: `{a..e}`.
+/// - Steps: `{1..10..3}` becomes `1 4 7 10`, `{a..e..2}` becomes `a c e`.
+///
+/// A brace without a comma or a valid range (e.g., `{a}` or `{}`) is kept literally,
+/// as is a brace following a `$`.
+///
+/// # Arguments
+/// - `word`: The pieces of the word.
+///
+/// # Returns
+/// - The expanded words, or the word itself if it has nothing to expand.
+pub fn expand_braces<T: Clone>(word: &[Piece<T>]) -> Vec<Vec<Piece<T>>> {
+    for (open, piece) in word.iter().enumerate() {
+        let is_dollar = open > 0 && matches!(word[open - 1], Piece::Char('$'));
+
+        if !matches!(piece, Piece::Char('{')) || is_dollar {
+            continue;
+        }
+
+        let Some((close, alternatives)) = parse_brace(word, open) else {
+            continue;
+        };
+
+        let mut words = Vec::new();
+
+        for alternative in alternatives {
+            let mut expanded = word[..open].to_vec();
+
+            expanded.extend(alternative);
+
+            expanded.extend_from_slice(&word[close + 1..]);
+
+            words.extend(expand_braces(&expanded));
+        }
+
+        return words;
+    }
+
+    vec![word.to_vec()]
+}
+
+/// Parses the brace opened at `open` and returns the position of its `}` and its alternatives.
+///
+/// Returns `None` if the brace is not closed or has neither a comma nor a valid range.
+fn parse_brace<T: Clone>(word: &[Piece<T>], open: usize) -> Option<(usize, Vec<Vec<Piece<T>>>)> {
+    let mut depth = 0usize;
+
+    let mut commas = Vec::new();
+
+    let mut close = None;
+
+    for (i, piece) in word.iter().enumerate().skip(open + 1) {
+        match piece {
+            Piece::Char('{') => depth += 1,
+
+            Piece::Char('}') if depth == 0 => {
+                close = Some(i);
+                break;
+            }
+
+            Piece::Char('}') => depth -= 1,
+
+            Piece::Char(',') if depth == 0 => commas.push(i),
+
+            _ => {}
+        }
+    }
+
+    let close = close?;
+
+    if commas.is_empty() {
+        let inner = word[open + 1..close]
+            .iter()
+            .map(|piece| match piece {
+                Piece::Char(c) => Some(*c),
+                Piece::Opaque(_) => None,
+            })
+            .collect::<Option<String>>()?;
+
+        let alternatives = expand_range(&inner)?
+            .into_iter()
+            .map(|item| item.chars().map(Piece::Char).collect())
+            .collect();
+
+        return Some((close, alternatives));
+    }
+
+    let mut alternatives = Vec::with_capacity(commas.len() + 1);
+
+    let mut start = open + 1;
+
+    for end in commas.into_iter().chain([close]) {
+        alternatives.push(word[start..end].to_vec());
+
+        start = end + 1;
+    }
+
+    Some((close, alternatives))
+}
+
+/// Expands a range such as `1..10`, `01..10`, `a..z` or `1..10..2` into its items.
+///
+/// Returns `None` if the text is not a valid range.
+fn expand_range(range: &str) -> Option<Vec<String>> {
+    let parts = range.split("..").collect::<Vec<_>>();
+
+    let (start, end, step) = match parts.as_slice() {
+        [start, end] => (*start, *end, 1),
+        [start, end, step] => (
+            *start,
+            *end,
+            step.parse::<i64>().ok()?.unsigned_abs().max(1),
+        ),
+        _ => return None,
+    };
+
+    if let (Ok(first), Ok(last)) = (start.parse::<i64>(), end.parse::<i64>()) {
+        let is_padded = [start, end].iter().any(|endpoint| {
+            let digits = endpoint.trim_start_matches(['-', '+']);
+
+            digits.len() > 1 && digits.starts_with('0')
+        });
+
+        let width = if is_padded {
+            start.len().max(end.len())
+        } else {
+            0
+        };
+
+        let items = step_between(first, last, step)
+            .map(|number| format!("{number:0width$}"))
+            .collect();
+
+        return Some(items);
+    }
+
+    let (mut start, mut end) = (start.chars(), end.chars());
+
+    match (start.next(), start.next(), end.next(), end.next()) {
+        (Some(first), None, Some(last), None)
+            if first.is_ascii_alphabetic() && last.is_ascii_alphabetic() =>
+        {
+            Some(
+                step_between(first as i64, last as i64, step)
+                    .map(|code| char::from(code as u8).to_string())
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Returns the numbers from `first` to `last` (inclusive) in steps of `step`, in either direction.
+fn step_between(first: i64, last: i64, step: u64) -> impl Iterator<Item = i64> {
+    let count = first.abs_diff(last) / step + 1;
+
+    let step = if first <= last {
+        step as i64
+    } else {
+        -(step as i64)
+    };
+
+    (0..count as i64).map(move |i| first + i * step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(word: &str) -> Vec<String> {
+        let word = word.chars().map(Piece::Char).collect::<Vec<Piece<()>>>();
+
+        expand_braces(&word)
+            .into_iter()
+            .map(|word| {
+                word.into_iter()
+                    .map(|piece| match piece {
+                        Piece::Char(c) => c,
+                        Piece::Opaque(_) => '?',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_braces_alternatives() {
+        assert_eq!(expand("file.{rs,toml}"), ["file.rs", "file.toml"]);
+
+        assert_eq!(expand("a{,b{1,2}}c"), ["ac", "ab1c", "ab2c"]);
+
+        assert_eq!(expand("{a,b}{1,2}"), ["a1", "a2", "b1", "b2"]);
+
+        assert_eq!(expand("{a}"), ["{a}"]);
+
+        assert_eq!(expand("{}"), ["{}"]);
+
+        assert_eq!(expand("{a,b"), ["{a,b"]);
+
+        assert_eq!(expand("${a,b}"), ["${a,b}"]);
+
+        assert_eq!(expand("{x{a,b}"), ["{xa", "{xb"]);
+    }
+
+    #[test]
+    fn test_expand_braces_ranges() {
+        assert_eq!(
+            expand("img{1..3}.png"),
+            ["img1.png", "img2.png", "img3.png"]
+        );
+
+        assert_eq!(expand("{3..1}"), ["3", "2", "1"]);
+
+        assert_eq!(expand("{-1..1}"), ["-1", "0", "1"]);
+
+        assert_eq!(expand("{08..10}"), ["08", "09", "10"]);
+
+        assert_eq!(expand("{1..010}").first().unwrap(), "001");
+
+        assert_eq!(expand("{1..10..3}"), ["1", "4", "7", "10"]);
+
+        assert_eq!(expand("{10..1..-4}"), ["10", "6", "2"]);
+
+        assert_eq!(expand("{a..e..2}"), ["a", "c", "e"]);
+
+        assert_eq!(expand("{Z..X}"), ["Z", "Y", "X"]);
+
+        assert_eq!(expand("{1..a}"), ["{1..a}"]);
+
+        assert_eq!(expand("{1..2..x}"), ["{1..2..x}"]);
+    }
+
+    #[test]
+    fn test_expand_braces_opaque() {
+        let word = [
+            Piece::Char('{'),
+            Piece::Opaque(1),
+            Piece::Char(','),
+            Piece::Char('b'),
+            Piece::Char('}'),
+        ];
+
+        assert_eq!(
+            expand_braces(&word),
+            vec![vec![Piece::Opaque(1)], vec![Piece::Char('b')]]
+        );
+
+        // an opaque piece is not part of a range.
+        let word = [
+            Piece::Char('{'),
+            Piece::Opaque(1),
+            Piece::Char('.'),
+            Piece::Char('.'),
+            Piece::Char('3'),
+            Piece::Char('}'),
+        ];
+
+        assert_eq!(expand_braces(&word), vec![word.to_vec()]);
+    }
+}
@@ -0,0 +1,525 @@
+use crate::error::*;
+
+use std::{ffi::OsString, fs, io, path::Path};
+
+use ::glob::{MatchOptions, Pattern};
+
+/// The options of pathname expansion, set with `set -o`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobOptions {
+    /// `dotglob`: patterns also match names starting with a `.` (but never `.` or `..`).
+    pub is_dotglob: bool,
+
+    /// `nocaseglob`: patterns match names regardless of case.
+    pub is_nocaseglob: bool,
+
+    /// `globstar`: a `**` path component matches any number of directories, including none
+    /// (e.g., `src/**` matches `src/` and everything below it).
+    pub is_globstar: bool,
+}
+
+/// A path component of a glob pattern.
+enum Component {
+    /// A name without special characters, used as it is.
+    Literal(String),
+
+    /// A `**` component with the `globstar` option set.
+    Globstar,
+
+    /// A pattern matched against the names in a directory.
+    Pattern(ComponentPattern),
+}
+
+/// The pattern of a path component, with an optional extended negation (e.g., `!(*.o|*.a)`).
+struct ComponentPattern {
+    /// The component as written, to tell whether it starts with a literal `.`.
+    text: String,
+
+    /// The pattern before the negation, or of the whole component without one.
+    prefix: Pattern,
+
+    /// The alternatives of the negation and the pattern after it.
+    negation: Option<(Vec<Pattern>, Pattern)>,
+}
+
+/// Returns `true` if the text contains a glob character (`*`, `?` or `[`) or a negation `!(`.
+pub fn has_glob_chars(text: &str) -> bool {
+    text.contains(['*', '?', '[']) || text.contains("!(")
+}
+
+/// Escapes the special characters of a pattern (`*`, `?`, `[`, `]` and `(`),
+/// so it matches the text literally.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '*' | '?' | '[' | ']' | '(' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Expands a glob pattern into the paths it matches.
+///
+/// Besides `*`, `?` and `[...]`, a path component may contain a negation `!(pattern|pattern)`,
+/// which matches any text not matched by one of the patterns (e.g., `!(*.o)`).
+/// Names starting with a `.` are only matched by a component starting with a `.`,
+/// unless `dotglob` is set. A pattern ending in `/` only matches directories.
+///
+/// # Arguments
+/// - `pattern`: The pattern, relative to `dir` unless it is absolute.
+/// - `dir`: The directory in which a relative pattern is expanded.
+/// - `options`: The glob options.
+///
+/// # Returns
+/// - The matching paths, sorted, and the errors for the directories that could not be read
+///   and the matching names that are not valid UTF-8.
+pub fn glob(pattern: &str, dir: &Path, options: GlobOptions) -> (Vec<String>, Vec<Error>) {
+    let mut errors = Vec::new();
+
+    let (root, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (String::from("/"), rest),
+        None => (String::new(), pattern),
+    };
+
+    let is_dir_only = rest.ends_with('/');
+
+    let components = rest
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .map(|component| parse_component(component, options))
+        .collect::<Option<Vec<_>>>();
+
+    let Some(components) = components else {
+        return (Vec::new(), errors);
+    };
+
+    let mut paths = vec![root];
+
+    for (index, component) in components.iter().enumerate() {
+        let is_last = index + 1 == components.len();
+
+        let mut matches = Vec::new();
+
+        for path in &paths {
+            match component {
+                Component::Literal(name) => {
+                    let joined = join(path, name);
+
+                    let metadata = fs::metadata(dir.join(&joined));
+
+                    if metadata.is_ok_and(|metadata| is_last || metadata.is_dir()) {
+                        matches.push(joined);
+                    }
+                }
+
+                Component::Globstar => {
+                    // `**` also matches no directory at all, so that, as in bash, `src/**/*.rs`
+                    // matches `src/lib.rs` and `src/**` matches `src/` itself.
+                    if !is_last || (is_dir_only && !path.is_empty()) {
+                        matches.push(path.clone());
+                    } else if !path.is_empty() {
+                        matches.push(join(path, ""));
+                    }
+
+                    descend(path, dir, options, is_last, &mut matches, &mut errors);
+                }
+
+                Component::Pattern(pattern) => {
+                    for name in read_names(path, dir, &mut errors) {
+                        if !pattern.matches(&name.to_string_lossy(), options) {
+                            continue;
+                        }
+
+                        let Some(name) = name.to_str() else {
+                            errors.push(Error::new(
+                                ErrorKind::Other,
+                                format!(
+                                    "{}: file name is not valid UTF-8",
+                                    join(path, &name.to_string_lossy())
+                                ),
+                            ));
+
+                            continue;
+                        };
+
+                        let joined = join(path, name);
+
+                        if is_last || dir.join(&joined).is_dir() {
+                            matches.push(joined);
+                        }
+                    }
+                }
+            }
+        }
+
+        paths = matches;
+    }
+
+    if is_dir_only {
+        paths = paths
+            .into_iter()
+            .filter(|path| dir.join(path).is_dir())
+            .map(|path| path + "/")
+            .collect();
+    }
+
+    // the root alone (e.g., for the pattern `/`) is not a match.
+    paths.retain(|path| !path.is_empty() && (path != "/" || components.is_empty()));
+
+    paths.sort();
+
+    paths.dedup();
+
+    (paths, errors)
+}
+
+/// Parses a path component of a pattern.
+///
+/// Returns `None` if the component is not a valid pattern.
+fn parse_component(text: &str, options: GlobOptions) -> Option<Component> {
+    if options.is_globstar && text == "**" {
+        return Some(Component::Globstar);
+    }
+
+    if !has_glob_chars(text) {
+        return Some(Component::Literal(text.to_string()));
+    }
+
+    let negation = text
+        .find("!(")
+        .and_then(|start| find_close(text, start + 2).map(|end| (start, end)));
+
+    let pattern = match negation {
+        Some((start, end)) => ComponentPattern {
+            text: text.to_string(),
+            prefix: compile(&text[..start])?,
+            negation: Some((
+                split_alternatives(&text[start + 2..end])
+                    .into_iter()
+                    .map(compile)
+                    .collect::<Option<Vec<_>>>()?,
+                compile(&text[end + 1..])?,
+            )),
+        },
+        None => ComponentPattern {
+            text: text.to_string(),
+            prefix: compile(text)?,
+            negation: None,
+        },
+    };
+
+    Some(Component::Pattern(pattern))
+}
+
+/// Returns the position of the `)` closing the parenthesis opened before `start`.
+fn find_close(text: &str, start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(start + i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Splits the inside of a negation at the `|` characters outside of parentheses.
+fn split_alternatives(text: &str) -> Vec<&str> {
+    let mut alternatives = Vec::new();
+
+    let mut depth = 0usize;
+
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => {
+                alternatives.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    alternatives.push(&text[start..]);
+
+    alternatives
+}
+
+/// Compiles a pattern within a path component. A `**` is the same as `*` there.
+fn compile(text: &str) -> Option<Pattern> {
+    let mut text = text.to_string();
+
+    while text.contains("**") {
+        text = text.replace("**", "*");
+    }
+
+    Pattern::new(&text).ok()
+}
+
+impl ComponentPattern {
+    /// Returns `true` if the pattern matches a name in a directory.
+    fn matches(&self, name: &str, options: GlobOptions) -> bool {
+        if name.starts_with('.') && !options.is_dotglob && !self.text.starts_with('.') {
+            return false;
+        }
+
+        let options = MatchOptions {
+            case_sensitive: !options.is_nocaseglob,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+
+        let Some((alternatives, suffix)) = &self.negation else {
+            return self.prefix.matches_with(name, options);
+        };
+
+        let boundaries = name
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([name.len()])
+            .collect::<Vec<_>>();
+
+        // the name matches if it splits into a match of the prefix, text not matched
+        // by any alternative, and a match of the suffix.
+        boundaries.iter().enumerate().any(|(index, &start)| {
+            self.prefix.matches_with(&name[..start], options)
+                && boundaries[index..].iter().any(|&end| {
+                    suffix.matches_with(&name[end..], options)
+                        && !alternatives
+                            .iter()
+                            .any(|alternative| alternative.matches_with(&name[start..end], options))
+                })
+        })
+    }
+}
+
+/// Adds the paths below `path` to `matches`, recursively, for a `**` component.
+///
+/// Only directories are added unless `is_last` is set. Symbolic links to directories are
+/// not followed.
+fn descend(
+    path: &str,
+    dir: &Path,
+    options: GlobOptions,
+    is_last: bool,
+    matches: &mut Vec<String>,
+    errors: &mut Vec<Error>,
+) {
+    for name in read_names(path, dir, errors) {
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+
+        if name.starts_with('.') && !options.is_dotglob {
+            continue;
+        }
+
+        let joined = join(path, name);
+
+        let is_dir = fs::symlink_metadata(dir.join(&joined)).is_ok_and(|m| m.is_dir());
+
+        if is_last || is_dir {
+            matches.push(joined.clone());
+        }
+
+        if is_dir {
+            descend(&joined, dir, options, is_last, matches, errors);
+        }
+    }
+}
+
+/// Returns the names in the directory at `path`.
+///
+/// A missing path or one that is not a directory has no names. Other errors,
+/// such as a directory that cannot be read, are added to `errors`.
+fn read_names(path: &str, dir: &Path, errors: &mut Vec<Error>) -> Vec<OsString> {
+    let display = match path {
+        "" => ".",
+        path => path,
+    };
+
+    let report = |err: io::Error| Error::new(ErrorKind::Other, format!("{display}: {err}"));
+
+    match fs::read_dir(dir.join(display)) {
+        Ok(entries) => entries
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry.file_name()),
+                Err(err) => {
+                    errors.push(report(err));
+                    None
+                }
+            })
+            .collect(),
+
+        Err(err)
+            if matches!(
+                err.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+            ) =>
+        {
+            Vec::new()
+        }
+
+        Err(err) => {
+            errors.push(report(err));
+            Vec::new()
+        }
+    }
+}
+
+/// Joins a name to a path of a match.
+fn join(path: &str, name: &str) -> String {
+    match path {
+        "" => name.to_string(),
+        path if path.ends_with('/') => format!("{path}{name}"),
+        path => format!("{path}/{name}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, path::PathBuf, process};
+
+    fn setup(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("fsh-glob-{name}-{}", process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+
+        for path in ["src/lexer", "src/.cache", "target"] {
+            fs::create_dir_all(dir.join(path)).unwrap();
+        }
+
+        for path in [
+            "main.rs",
+            "Cargo.toml",
+            ".hidden",
+            "main.o",
+            "src/lib.rs",
+            "src/lexer/token.rs",
+            "src/.cache/x.rs",
+        ] {
+            fs::write(dir.join(path), "").unwrap();
+        }
+
+        dir
+    }
+
+    fn matches(pattern: &str, dir: &Path, options: GlobOptions) -> Vec<String> {
+        let (paths, errors) = glob(pattern, dir, options);
+
+        assert!(errors.is_empty());
+
+        paths
+    }
+
+    #[test]
+    fn test_glob_options() {
+        let dir = setup("options");
+
+        let options = GlobOptions::default();
+
+        assert_eq!(
+            matches("*", &dir, options),
+            ["Cargo.toml", "main.o", "main.rs", "src", "target"]
+        );
+
+        assert_eq!(matches("*/", &dir, options), ["src/", "target/"]);
+
+        assert_eq!(matches(".*", &dir, options), [".hidden"]);
+
+        let dotglob = GlobOptions {
+            is_dotglob: true,
+            ..options
+        };
+
+        assert_eq!(
+            matches("*.*", &dir, dotglob),
+            [".hidden", "Cargo.toml", "main.o", "main.rs"]
+        );
+
+        let nocaseglob = GlobOptions {
+            is_nocaseglob: true,
+            ..options
+        };
+
+        assert_eq!(matches("cargo.*", &dir, nocaseglob), ["Cargo.toml"]);
+
+        assert!(matches("cargo.*", &dir, options).is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_glob_globstar() {
+        let dir = setup("globstar");
+
+        let options = GlobOptions::default();
+
+        // without globstar, `**` is the same as `*`.
+        assert_eq!(matches("**/*.rs", &dir, options), ["src/lib.rs"]);
+
+        let globstar = GlobOptions {
+            is_globstar: true,
+            ..options
+        };
+
+        assert_eq!(
+            matches("**/*.rs", &dir, globstar),
+            ["main.rs", "src/lexer/token.rs", "src/lib.rs"]
+        );
+
+        assert_eq!(
+            matches("src/**", &dir, globstar),
+            ["src/", "src/lexer", "src/lexer/token.rs", "src/lib.rs"]
+        );
+
+        assert_eq!(matches("src/**/", &dir, globstar), ["src/", "src/lexer/"]);
+
+        assert_eq!(
+            matches("**/", &dir, globstar),
+            ["src/", "src/lexer/", "target/"]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_glob_negation() {
+        let dir = setup("negation");
+
+        let options = GlobOptions::default();
+
+        assert_eq!(
+            matches("!(*.o)", &dir, options),
+            ["Cargo.toml", "main.rs", "src", "target"]
+        );
+
+        assert_eq!(matches("main.!(o)", &dir, options), ["main.rs"]);
+
+        assert_eq!(
+            matches("!(*.o|*.rs|src)", &dir, options),
+            ["Cargo.toml", "target"]
+        );
+
+        assert!(matches(&escape("main.*"), &dir, options).is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
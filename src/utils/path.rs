@@ -2,8 +2,11 @@ use crate::{error::*, result::*};
 
 use std::{
     env::home_dir,
-    ffi::OsStr,
+    ffi::{CStr, CString, OsStr},
+    mem,
+    os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
+    ptr,
 };
 
 /// Resolves a target path relative to a given current path, returning the canonical absolute path.
@@ -52,19 +55,79 @@ pub fn resolve_relative<A: AsRef<OsStr> + ?Sized, B: AsRef<OsStr> + ?Sized>(
 
 /// Expands a leading tilde (`~`) in the given path to the user's home directory.
 ///
-/// This function replaces all instances of `~` in the input string with the user's home directory
-/// path, as determined by the `home_dir()` function. The result is returned as a `PathBuf`.
+/// Only a tilde at the start of the path is expanded, up to the first `/`: `~` and `~/x` use
+/// the home directory as determined by the `home_dir()` function, and `~user` or `~user/x`
+/// the home directory of `user` (see `user_home_dir`). Any other tilde is kept as it is.
 ///
-/// If the home directory cannot be determined, an empty path is substituted instead.
+/// If the home directory cannot be determined, the path is returned unchanged.
 pub fn expand_tilde_to_home_dir(path: impl Into<String>) -> PathBuf {
-    path.into()
-        .replace(
-            '~',
-            &home_dir()
-                .unwrap_or(String::default().into())
-                .to_string_lossy(),
-        )
-        .into()
+    let path = path.into();
+
+    let Some(rest) = path.strip_prefix('~') else {
+        return path.into();
+    };
+
+    let (user, suffix) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+
+    let home = match user {
+        "" => home_dir(),
+        user => user_home_dir(user),
+    };
+
+    match home {
+        Some(home) => PathBuf::from(format!("{}{suffix}", home.to_string_lossy())),
+        None => path.into(),
+    }
+}
+
+/// The largest buffer used to look up an entry of the user database.
+const MAX_PASSWD_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Returns the home directory of the given user, as found in the user database.
+///
+/// # Arguments
+/// - `user`: The login name of the user.
+///
+/// # Returns
+/// - `Some(PathBuf)` if the user exists.
+/// - `None` otherwise.
+pub fn user_home_dir(user: &str) -> Option<PathBuf> {
+    let name = CString::new(user).ok()?;
+
+    let mut passwd: libc::passwd = unsafe { mem::zeroed() };
+
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+
+    let mut result = ptr::null_mut();
+
+    loop {
+        let code = unsafe {
+            libc::getpwnam_r(
+                name.as_ptr(),
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+
+        // an entry too large for the buffer (`ERANGE`) is looked up again with a larger one.
+        match code {
+            libc::ERANGE if buffer.len() < MAX_PASSWD_BUFFER_SIZE => {
+                buffer.resize(buffer.len() * 2, 0);
+            }
+            0 => break,
+            _ => return None,
+        }
+    }
+
+    if result.is_null() || passwd.pw_dir.is_null() {
+        return None;
+    }
+
+    let dir = unsafe { CStr::from_ptr(passwd.pw_dir) };
+
+    Some(PathBuf::from(OsStr::from_bytes(dir.to_bytes())))
 }
 
 #[cfg(test)]
@@ -73,42 +136,55 @@ mod tests {
 
     #[test]
     fn test_expand_tilde_to_home_dir() {
-        // const PATH: &str = "/Users/<you-name>";
-        const PATH: &str = "/Users/flucium";
+        let home = home_dir().unwrap();
+
+        let path = home.to_string_lossy();
 
         assert_eq!(
             expand_tilde_to_home_dir("~"),
-            PathBuf::from(format!("{PATH}"))
+            PathBuf::from(format!("{path}"))
         );
 
         assert_eq!(
             expand_tilde_to_home_dir("~/"),
-            PathBuf::from(format!("{PATH}"))
+            PathBuf::from(format!("{path}"))
         );
 
         assert_eq!(
             expand_tilde_to_home_dir("~"),
-            PathBuf::from(format!("{PATH}/"))
+            PathBuf::from(format!("{path}/"))
         );
 
         assert_eq!(
             expand_tilde_to_home_dir("~/"),
-            PathBuf::from(format!("{PATH}/"))
+            PathBuf::from(format!("{path}/"))
         );
 
         assert_eq!(
             expand_tilde_to_home_dir("~///"),
-            PathBuf::from(format!("{PATH}"))
+            PathBuf::from(format!("{path}"))
         );
 
         assert_eq!(
             expand_tilde_to_home_dir("~"),
-            PathBuf::from(format!("{PATH}///"))
+            PathBuf::from(format!("{path}///"))
         );
 
         assert_eq!(
             expand_tilde_to_home_dir("~///"),
-            PathBuf::from(format!("{PATH}///"))
+            PathBuf::from(format!("{path}///"))
+        );
+
+        assert_eq!(
+            expand_tilde_to_home_dir("~/src/~"),
+            PathBuf::from(format!("{path}/src/~"))
+        );
+
+        assert_eq!(expand_tilde_to_home_dir("a/~"), PathBuf::from("a/~"));
+
+        assert_eq!(
+            expand_tilde_to_home_dir("~no-such-user-fsh/x"),
+            PathBuf::from("~no-such-user-fsh/x")
         );
     }
 